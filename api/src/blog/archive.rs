@@ -0,0 +1,211 @@
+use crate::blog::config::Config;
+use crate::blog::error::ApiError;
+use crate::blog::upload::{check_type, finish_file, sniff_type, UploadedFile, SNIFF_SIZE};
+use crate::blog::utils::join_relative;
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use tide::StatusCode;
+
+const ARCHIVE_FILE: &str = "archive";
+const ENTRY_FILE: &str = "entry";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchivePlan {
+    // target paths relative to the posts folder
+    pub files: Vec<String>,
+    pub conflicts: Vec<String>,
+}
+
+// a received archive in its own folder below the data path, removed when dropped
+pub struct Archive {
+    path: PathBuf,
+    format: ArchiveFormat,
+}
+
+impl Archive {
+    pub fn create(config: &Config) -> Result<(Archive, fs::File), ApiError> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let path = config
+            .get_archives_path()
+            .join(format!("{}-{:09}", now.as_secs(), now.subsec_nanos()));
+        let file = match fs::create_dir_all(&path).and_then(|_| fs::File::create(path.join(ARCHIVE_FILE))) {
+            Ok(file) => file,
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to store archive: {}", e)));
+            }
+        };
+        Ok((Archive { path, format: ArchiveFormat::Zip }, file))
+    }
+
+    // detects the format once the archive is completely received
+    pub fn detect_format(&mut self) -> Result<(), ApiError> {
+        let mut header = vec![0u8; 512];
+        let header_size = match fs::File::open(self.path.join(ARCHIVE_FILE)).and_then(|mut file| file.read(&mut header)) {
+            Ok(header_size) => header_size,
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read archive: {}", e)));
+            }
+        };
+        self.format = match sniff_type(&header[..header_size], "") {
+            Some("zip") => ArchiveFormat::Zip,
+            Some("tar") => ArchiveFormat::Tar,
+            Some("gz") => ArchiveFormat::TarGz,
+            _ => {
+                return Err(ApiError::new(StatusCode::UnsupportedMediaType, String::from("archive must be a zip, tar or tar.gz file")));
+            }
+        };
+        Ok(())
+    }
+
+    // lists the files to extract into folder, entries leaving the folder or links reject the whole archive
+    pub fn plan(&self, config: &Config, folder: &str) -> Result<ArchivePlan, ApiError> {
+        let mut plan = ArchivePlan { files: vec![], conflicts: vec![] };
+        self.visit_entries(config, &mut |name, _| {
+            let target = target_name(folder, name.as_str());
+            let path = match join_relative(&config.get_input_path(), target.as_str()) {
+                Some(path) => path,
+                None => {
+                    return Err(ApiError::new(StatusCode::UnprocessableEntity, format!("invalid archive entry: {}", name)));
+                }
+            };
+            if path.exists() {
+                plan.conflicts.push(target.clone());
+            }
+            plan.files.push(target);
+            Ok(())
+        })?;
+        Ok(plan)
+    }
+
+    // extracts all files, every file passes the same checks as a regular upload;
+    // on failure the already extracted files are removed again
+    pub fn extract(&self, config: &Config, folder: &str, process_images: bool) -> Result<Vec<UploadedFile>, ApiError> {
+        let entry_path = self.path.join(ENTRY_FILE);
+        let mut uploaded_files: Vec<UploadedFile> = vec![];
+        let mut total_size: u64 = 0;
+        let result = self.visit_entries(config, &mut |name, reader| {
+            let target = target_name(folder, name.as_str());
+            let to_error = |e: io::Error| ApiError::new(StatusCode::InternalServerError, format!("unable to extract {}: {}", name, e));
+
+            // the type is detected first, so at most one byte more than its limit is extracted
+            let mut header = vec![];
+            reader.take(SNIFF_SIZE as u64).read_to_end(&mut header).map_err(to_error)?;
+            let (_, limit) = check_type(&config.upload_limits, target.as_str(), &header, header.len() as u64)?;
+            let mut file = fs::File::create(&entry_path).map_err(to_error)?;
+            file.write_all(&header).map_err(to_error)?;
+            let size = header.len() as u64 + io::copy(&mut reader.take(limit + 1 - header.len() as u64), &mut file).map_err(to_error)?;
+            drop(file);
+
+            total_size += size;
+            if total_size > config.archive_max_extracted_size {
+                return Err(ApiError::new(
+                    StatusCode::PayloadTooLarge,
+                    format!("extracted files exceed the limit of {} bytes", config.archive_max_extracted_size),
+                ));
+            }
+            uploaded_files.push(finish_file(config, target.as_str(), &entry_path, process_images)?);
+            Ok(())
+        });
+
+        if let Err(e) = result {
+            for uploaded_file in uploaded_files.iter() {
+                let _ = fs::remove_file(config.get_input_path().join(uploaded_file.name.as_str()));
+            }
+            return Err(e);
+        }
+        Ok(uploaded_files)
+    }
+
+    // calls the visitor with the name and content of every file entry, folders are skipped
+    fn visit_entries(
+        &self,
+        config: &Config,
+        visitor: &mut dyn FnMut(String, &mut dyn Read) -> Result<(), ApiError>,
+    ) -> Result<(), ApiError> {
+        let invalid = |e: &dyn std::fmt::Display| ApiError::new(StatusCode::UnprocessableEntity, format!("invalid archive: {}", e));
+        let too_many = || {
+            ApiError::new(
+                StatusCode::PayloadTooLarge,
+                format!("archive has more than {} entries", config.archive_max_entries),
+            )
+        };
+        let file = match fs::File::open(self.path.join(ARCHIVE_FILE)) {
+            Ok(file) => file,
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read archive: {}", e)));
+            }
+        };
+
+        match self.format {
+            ArchiveFormat::Zip => {
+                let mut zip = zip::ZipArchive::new(file).map_err(|e| invalid(&e))?;
+                if zip.len() as u64 > config.archive_max_entries {
+                    return Err(too_many());
+                }
+                for idx in 0..zip.len() {
+                    let mut entry = zip.by_index(idx).map_err(|e| invalid(&e))?;
+                    let name = entry.name().to_string();
+                    if entry.is_symlink() {
+                        return Err(ApiError::new(StatusCode::UnprocessableEntity, format!("archive contains a symlink: {}", name)));
+                    }
+                    if entry.is_dir() {
+                        continue;
+                    }
+                    visitor(name, &mut entry)?;
+                }
+            }
+            ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+                let reader: Box<dyn Read> = if self.format == ArchiveFormat::TarGz {
+                    Box::new(GzDecoder::new(file))
+                } else {
+                    Box::new(file)
+                };
+                let mut tar = tar::Archive::new(reader);
+                for (idx, entry) in tar.entries().map_err(|e| invalid(&e))?.enumerate() {
+                    if idx as u64 >= config.archive_max_entries {
+                        return Err(too_many());
+                    }
+                    let mut entry = entry.map_err(|e| invalid(&e))?;
+                    let name = entry.path().map_err(|e| invalid(&e))?.to_string_lossy().to_string();
+                    match entry.header().entry_type() {
+                        tar::EntryType::Regular | tar::EntryType::Continuous => visitor(name, &mut entry)?,
+                        tar::EntryType::Directory => {}
+                        _ => {
+                            return Err(ApiError::new(
+                                StatusCode::UnprocessableEntity,
+                                format!("archive contains a link or special file: {}", name),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Archive {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+fn target_name(folder: &str, name: &str) -> String {
+    if folder.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", folder.trim_end_matches('/'), name)
+    }
+}
@@ -0,0 +1,142 @@
+use crate::blog::config::Config;
+use crate::blog::error::ApiError;
+use crate::blog::generator::{find_unused_files, Generator, Post};
+use crate::blog::upload::{get_extension, sniff_type};
+use crate::blog::utils::{find_files, get_last_commits, CommitSummary};
+use git2::Repository;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use tera::Tera;
+use tide::StatusCode;
+
+const SNIFF_SIZE: usize = 512;
+
+// mime types of the sniffed upload types and common text files
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("jpg", "image/jpeg"),
+    ("png", "image/png"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("svg", "image/svg+xml"),
+    ("pdf", "application/pdf"),
+    ("stl", "model/stl"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("txt", "text/plain"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+];
+
+#[derive(Debug, Serialize)]
+pub struct Asset {
+    pub name: String,
+    pub size: u64,
+    pub mime: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    // none for files that were never committed
+    pub last_commit: Option<CommitSummary>,
+    pub used_by: Vec<String>,
+    pub orphan: bool,
+}
+
+// all files below the posts folder except markdown and html files
+pub fn get_assets(config: &Config) -> Result<Vec<Asset>, ApiError> {
+    let input_path = config.get_input_path();
+    let files = find_files(&input_path, None);
+    let posts = scan_posts(config)?;
+
+    let mut used_by: HashMap<String, Vec<String>> = HashMap::new();
+    for post in posts.iter() {
+        for file in post.get_referenced_files() {
+            let posts = used_by.entry(file).or_insert(vec![]);
+            if !posts.contains(post.get_filename()) {
+                posts.push(post.get_filename().clone());
+            }
+        }
+    }
+    let tag_list: Vec<String> = posts.iter().flat_map(|post| post.get_tags().clone()).collect();
+    let orphans: HashSet<String> = find_unused_files(&files, &posts, &tag_list).into_iter().collect();
+
+    let names: HashSet<String> = files
+        .iter()
+        .filter(|file| !file.is_dir && !file.name.ends_with(".md") && !file.name.ends_with(".html"))
+        .map(|file| file.name.clone())
+        .collect();
+    let last_commits = match Repository::open(&input_path) {
+        Ok(repo) => match get_last_commits(&repo, &names) {
+            Ok(last_commits) => last_commits,
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read history: {}", e.message())));
+            }
+        },
+        Err(_) => HashMap::new(),
+    };
+
+    let mut assets = vec![];
+    let mut names: Vec<String> = names.into_iter().collect();
+    names.sort();
+    for name in names {
+        let path = input_path.join(name.as_str());
+        let size = fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+        let (width, height) = match image::image_dimensions(&path) {
+            Ok((width, height)) => (Some(width), Some(height)),
+            Err(_) => (None, None),
+        };
+        assets.push(Asset {
+            mime: get_mime_type(&path, name.as_str()),
+            size,
+            width,
+            height,
+            last_commit: last_commits.get(&name).cloned(),
+            used_by: used_by.remove(&name).unwrap_or(vec![]),
+            orphan: orphans.contains(&name),
+            name,
+        });
+    }
+    Ok(assets)
+}
+
+// parses all posts (including the custom ones) without rendering them
+fn scan_posts(config: &Config) -> Result<Vec<Post>, ApiError> {
+    let tera = Tera::default();
+    let generator = Generator::new(&tera, config.get_input_path(), config.get_output_path(), None);
+
+    let mut posts = vec![];
+    for file in find_files(&config.get_input_path(), Some(".md")) {
+        if file.is_dir {
+            continue;
+        }
+        let mut content = match fs::read_to_string(config.get_input_path().join(file.name.as_str())) {
+            Ok(content) => content,
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read {}: {}", file.name, e)));
+            }
+        };
+        match generator.new_post(file.name.clone(), &mut content) {
+            Ok(post) => posts.push(post),
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::UnprocessableEntity, format!("unable to parse {}: {}", file.name, e.message)));
+            }
+        }
+    }
+    Ok(posts)
+}
+
+// prefers the type sniffed from the content over the extension
+fn get_mime_type(path: &std::path::Path, name: &str) -> String {
+    let extension = get_extension(name);
+    let mut header = vec![0u8; SNIFF_SIZE];
+    let header_size = fs::File::open(path).and_then(|mut file| file.read(&mut header)).unwrap_or(0);
+    let kind = sniff_type(&header[..header_size], extension.as_str()).unwrap_or(extension.as_str());
+    match MIME_TYPES.iter().find(|(mime_kind, _)| *mime_kind == kind) {
+        Some((_, mime)) => mime.to_string(),
+        None => String::from("application/octet-stream"),
+    }
+}
@@ -3,6 +3,9 @@ use tide::http::mime;
 use tide::{Middleware, Next, Request, Response, StatusCode};
 
 const AUTH_HEADER_NAME: &str = "Authorization";
+// all clients share the token, so users only identify themselves
+const USER_HEADER_NAME: &str = "X-User";
+const DEFAULT_USER: &str = "default";
 
 pub struct AuthMiddleware {}
 
@@ -30,7 +33,7 @@ where
             return Ok(unauthorized("multiple auth headers"));
         }
 
-        let value = header_value.get(0).unwrap().to_string();
+        let value = header_value.first().unwrap().to_string();
         if !value.starts_with("Token ") {
             return Ok(unauthorized("invalid token type"));
         }
@@ -50,3 +53,20 @@ fn unauthorized(message: &str) -> Response {
         .content_type(mime::PLAIN)
         .build()
 }
+
+// returns the user of the request, reduced to characters safe for file names
+pub fn get_user<State>(req: &Request<State>) -> String {
+    let user: String = match req.header(USER_HEADER_NAME) {
+        Some(header) => header
+            .last()
+            .as_str()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect(),
+        None => String::new(),
+    };
+    if user.is_empty() {
+        return DEFAULT_USER.to_string();
+    }
+    user
+}
@@ -1,3 +1,8 @@
+use crate::blog::dates::is_valid_format;
+use crate::blog::jobs::Jobs;
+use crate::blog::lock::RepoLock;
+use serde::Serialize;
+use std::collections::HashMap;
 use std::env;
 use std::path::{Path, PathBuf};
 
@@ -5,17 +10,69 @@ pub const HIGHLIGHT_THEME: &str = "base16-ocean.dark";
 pub const DEFAULT_BRANCH: &str = "main";
 pub const REMOTE_NAME: &str = "ssh";
 pub const REF_NAME: &str = "refs/heads/main";
+// the remote branch as of the last fetch or push
+pub const TRACKING_REF_NAME: &str = "refs/remotes/ssh/main";
+pub const DEFAULT_DATA_PATH: &str = "~/.local/share/ohmyblog";
+pub const DEFAULT_TRASH_RETENTION_DAYS: u64 = 30;
+pub const DEFAULT_IMAGE_MAX_DIMENSION: u64 = 2560;
+pub const DEFAULT_JPEG_QUALITY: u64 = 85;
+pub const DEFAULT_ARCHIVE_MAX_SIZE: &str = "1G";
+pub const DEFAULT_ARCHIVE_MAX_EXTRACTED_SIZE: &str = "2G";
+pub const DEFAULT_ARCHIVE_MAX_ENTRIES: u64 = 1000;
+// allowed upload types (sniffed from the content) with their max size
+pub const DEFAULT_UPLOAD_LIMITS: &str = "jpg=20M,png=20M,gif=20M,webp=20M,pdf=50M,stl=200M,mp4=500M,webm=500M";
+// strftime format of created dates in the templates
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+// the statuses styled by the templates before statuses were configurable
+pub const DEFAULT_POST_STATUSES: &str = "done=Done:success,abandoned=Abandoned:danger";
 
 #[derive(Debug, Clone)]
 pub struct ConfigError {
     pub message: String,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum SigningFormat {
+    Ssh,
+    Gpg,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PostStatus {
+    pub name: String,
+    pub label: String,
+    pub colour: String,
+}
+
 #[derive(Clone)]
 pub struct Config {
     pub working_path: String,
     pub token: String,
     pub git_ssh_key_path: String,
+    pub validate_commits: bool,
+    pub signing_format: SigningFormat,
+    pub signing_key: Option<String>,
+    pub require_signing: bool,
+    pub data_path: String,
+    pub webhook_secret: Option<String>,
+    pub publish_repo_path: Option<String>,
+    pub publish_branch: Option<String>,
+    pub publish_dir: Option<String>,
+    pub trash_retention_days: u64,
+    pub upload_limits: HashMap<String, u64>,
+    pub archive_max_size: u64,
+    pub archive_max_extracted_size: u64,
+    pub archive_max_entries: u64,
+    pub process_images: bool,
+    pub image_max_dimension: u32,
+    pub jpeg_quality: u8,
+    pub statuses: Vec<PostStatus>,
+    // only configured statuses are enforced by the generator, with the defaults other statuses still render
+    pub strict_statuses: bool,
+    pub status_transitions: Vec<(String, String)>,
+    pub date_format: String,
+    pub jobs: Jobs,
+    pub repo_lock: RepoLock,
 }
 
 impl Config {
@@ -23,10 +80,76 @@ impl Config {
         let working_path = path_from_env("WORKING_PATH")?;
         let token = path_from_env("TOKEN")?;
         let git_ssh_key_path = path_from_env("GIT_SSH_KEY_PATH")?;
+        let validate_commits = bool_from_env("VALIDATE_COMMITS", false)?;
+        let signing_format = match get_optional_env("SIGNING_FORMAT").as_deref() {
+            None | Some("ssh") => SigningFormat::Ssh,
+            Some("gpg") => SigningFormat::Gpg,
+            Some(format) => {
+                return Err(ConfigError { message: format!("SIGNING_FORMAT must be ssh or gpg: {}", format) });
+            }
+        };
+        // ssh keys are paths, gpg keys are key ids
+        let signing_key = match signing_format {
+            SigningFormat::Ssh => optional_path_from_env("SIGNING_KEY"),
+            SigningFormat::Gpg => get_optional_env("SIGNING_KEY"),
+        };
+        let require_signing = bool_from_env("REQUIRE_SIGNING", false)?;
+        // everything below the working path is publicly served, so keep server state elsewhere
+        let data_path = optional_path_from_env("DATA_PATH")
+            .unwrap_or(shellexpand::full(DEFAULT_DATA_PATH).unwrap().into_owned());
+        let webhook_secret = get_optional_env("WEBHOOK_SECRET");
+        let publish_repo_path = optional_path_from_env("PUBLISH_REPO_PATH");
+        let publish_branch = get_optional_env("PUBLISH_BRANCH");
+        let publish_dir = optional_path_from_env("PUBLISH_DIR");
+        // 0 keeps deleted items until the trash is emptied
+        let trash_retention_days = u64_from_env("TRASH_RETENTION_DAYS", DEFAULT_TRASH_RETENTION_DAYS)?;
+        let upload_limits = size_map_from_env("UPLOAD_LIMITS", DEFAULT_UPLOAD_LIMITS)?;
+        let archive_max_size = size_from_env("ARCHIVE_MAX_SIZE", DEFAULT_ARCHIVE_MAX_SIZE)?;
+        // the total size of all files and the number of entries of an extracted archive
+        let archive_max_extracted_size = size_from_env("ARCHIVE_MAX_EXTRACTED_SIZE", DEFAULT_ARCHIVE_MAX_EXTRACTED_SIZE)?;
+        let archive_max_entries = u64_from_env("ARCHIVE_MAX_ENTRIES", DEFAULT_ARCHIVE_MAX_ENTRIES)?;
+        // downscales, orients and re-encodes uploaded images, which drops their metadata
+        let process_images = bool_from_env("PROCESS_IMAGES", false)?;
+        // 0 keeps the original dimensions
+        let image_max_dimension = u64_from_env("IMAGE_MAX_DIMENSION", DEFAULT_IMAGE_MAX_DIMENSION)?.min(u32::MAX as u64) as u32;
+        let jpeg_quality = u64_from_env("JPEG_QUALITY", DEFAULT_JPEG_QUALITY)?.clamp(1, 100) as u8;
+        // e.g. "planned=Planned:info,done=Done:success", the colour is a bulma color modifier
+        let strict_statuses = get_optional_env("POST_STATUSES").is_some();
+        let statuses = statuses_from_env("POST_STATUSES", DEFAULT_POST_STATUSES)?;
+        // e.g. "planned>done", unset allows every transition between the statuses
+        let status_transitions = transitions_from_env("STATUS_TRANSITIONS", &statuses)?;
+        let date_format = get_optional_env("DATE_FORMAT").unwrap_or(DEFAULT_DATE_FORMAT.to_string());
+        if !is_valid_format(date_format.as_str()) {
+            return Err(ConfigError { message: format!("DATE_FORMAT is not a valid strftime format: {}", date_format) });
+        }
+        let repo_lock = RepoLock::for_path(working_path.as_str());
         let config = Config {
             working_path,
             token,
             git_ssh_key_path,
+            validate_commits,
+            signing_format,
+            signing_key,
+            require_signing,
+            data_path,
+            webhook_secret,
+            publish_repo_path,
+            publish_branch,
+            publish_dir,
+            trash_retention_days,
+            upload_limits,
+            archive_max_size,
+            archive_max_extracted_size,
+            archive_max_entries,
+            process_images,
+            image_max_dimension,
+            jpeg_quality,
+            statuses,
+            strict_statuses,
+            status_transitions,
+            date_format,
+            jobs: Jobs::new(),
+            repo_lock,
         };
         Ok(config)
     }
@@ -38,6 +161,69 @@ impl Config {
     pub fn get_output_path(&self) -> PathBuf {
         Path::new(self.working_path.as_str()).join(Path::new("p"))
     }
+
+    pub fn get_deployments_path(&self) -> PathBuf {
+        Path::new(self.data_path.as_str()).join(Path::new("deployments.json"))
+    }
+
+    pub fn get_deploy_objects_path(&self) -> PathBuf {
+        Path::new(self.data_path.as_str()).join(Path::new("deploy_objects"))
+    }
+
+    // the branch is published into the posts repository unless another one is configured
+    pub fn get_publish_repo_path(&self) -> PathBuf {
+        match &self.publish_repo_path {
+            Some(publish_repo_path) => PathBuf::from(publish_repo_path),
+            None => self.get_input_path(),
+        }
+    }
+
+    pub fn get_drafts_path(&self) -> PathBuf {
+        Path::new(self.data_path.as_str()).join(Path::new("drafts"))
+    }
+
+    pub fn get_trash_path(&self) -> PathBuf {
+        Path::new(self.data_path.as_str()).join(Path::new("trash"))
+    }
+
+    pub fn get_upload_sessions_path(&self) -> PathBuf {
+        Path::new(self.data_path.as_str()).join(Path::new("uploads"))
+    }
+
+    pub fn get_upload_temp_path(&self) -> PathBuf {
+        Path::new(self.data_path.as_str()).join(Path::new("upload_temp"))
+    }
+
+    pub fn get_archives_path(&self) -> PathBuf {
+        Path::new(self.data_path.as_str()).join(Path::new("archives"))
+    }
+
+    pub fn get_merge_bases_path(&self) -> PathBuf {
+        Path::new(self.data_path.as_str()).join(Path::new("bases"))
+    }
+
+    pub fn get_job_log_path(&self) -> PathBuf {
+        Path::new(self.data_path.as_str()).join(Path::new("jobs.log"))
+    }
+
+    pub fn get_status_log_path(&self) -> PathBuf {
+        Path::new(self.data_path.as_str()).join(Path::new("status.log"))
+    }
+
+    pub fn get_status(&self, name: &str) -> Option<&PostStatus> {
+        self.statuses.iter().find(|status| status.name == name)
+    }
+
+    // setting or removing a status is always allowed
+    pub fn is_transition_allowed(&self, from: Option<&str>, to: Option<&str>) -> bool {
+        match (from, to) {
+            (Some(from), Some(to)) if from != to && !self.status_transitions.is_empty() => self
+                .status_transitions
+                .iter()
+                .any(|(allowed_from, allowed_to)| allowed_from == from && allowed_to == to),
+            _ => true,
+        }
+    }
 }
 
 pub trait ConfigType {
@@ -46,7 +232,7 @@ pub trait ConfigType {
 
 impl ConfigType for Config {
     fn get_token(&self) -> String {
-        return self.token.to_owned();
+        self.token.to_owned()
     }
 }
 
@@ -57,8 +243,139 @@ fn get_required_env(name: &str) -> Result<String, ConfigError> {
     }
 }
 
+fn get_optional_env(name: &str) -> Option<String> {
+    match env::var(name) {
+        Ok(env_val) if !env_val.is_empty() => Some(env_val),
+        _ => None,
+    }
+}
+
+fn optional_path_from_env(name: &str) -> Option<String> {
+    get_optional_env(name).map(|relative_path| shellexpand::full(&relative_path).unwrap().into_owned())
+}
+
 fn path_from_env(name: &str) -> Result<String, ConfigError> {
     let relative_path = get_required_env(name)?;
     let expanded_path = shellexpand::full(&relative_path).unwrap().into_owned();
     Ok(expanded_path)
-}
\ No newline at end of file
+}
+
+fn bool_from_env(name: &str, default: bool) -> Result<bool, ConfigError> {
+    match env::var(name) {
+        Ok(env_val) => match env_val.to_lowercase().as_str() {
+            "1" | "true" | "yes" => Ok(true),
+            "0" | "false" | "no" => Ok(false),
+            _ => Err(ConfigError { message: format!("{} environment variable is not a boolean: {}", name, env_val) })
+        },
+        Err(_) => Ok(default)
+    }
+}
+
+fn u64_from_env(name: &str, default: u64) -> Result<u64, ConfigError> {
+    match env::var(name) {
+        Ok(env_val) => match env_val.parse::<u64>() {
+            Ok(value) => Ok(value),
+            Err(_) => Err(ConfigError { message: format!("{} environment variable is not a number: {}", name, env_val) })
+        },
+        Err(_) => Ok(default)
+    }
+}
+
+// parses "name=size,..." with optional K, M or G suffixes on the sizes
+fn size_map_from_env(name: &str, default: &str) -> Result<HashMap<String, u64>, ConfigError> {
+    let env_val = get_optional_env(name).unwrap_or(default.to_string());
+    let mut sizes = HashMap::new();
+    for entry in env_val.split(',').map(|entry| entry.trim()).filter(|entry| !entry.is_empty()) {
+        let invalid = || ConfigError { message: format!("{} environment variable has an invalid entry: {}", name, entry) };
+        let (key, size) = entry.split_once('=').ok_or_else(invalid)?;
+        sizes.insert(key.trim().to_lowercase(), parse_size(size).ok_or_else(invalid)?);
+    }
+    Ok(sizes)
+}
+
+fn size_from_env(name: &str, default: &str) -> Result<u64, ConfigError> {
+    let env_val = get_optional_env(name).unwrap_or(default.to_string());
+    parse_size(env_val.as_str())
+        .ok_or_else(|| ConfigError { message: format!("{} environment variable is not a size: {}", name, env_val) })
+}
+
+fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim().to_uppercase();
+    let (number, factor) = match size.chars().last() {
+        Some('K') => (&size[..size.len() - 1], 1 << 10),
+        Some('M') => (&size[..size.len() - 1], 1 << 20),
+        Some('G') => (&size[..size.len() - 1], 1 << 30),
+        _ => (size.as_str(), 1),
+    };
+    number.parse::<u64>().ok().map(|number| number * factor)
+}
+
+// parses "name=label:colour,..."
+fn statuses_from_env(name: &str, default: &str) -> Result<Vec<PostStatus>, ConfigError> {
+    let env_val = get_optional_env(name).unwrap_or(default.to_string());
+    let mut statuses: Vec<PostStatus> = vec![];
+    for entry in env_val.split(',').map(|entry| entry.trim()).filter(|entry| !entry.is_empty()) {
+        let invalid = || ConfigError { message: format!("{} environment variable has an invalid entry: {}", name, entry) };
+        let (status, display) = entry.split_once('=').ok_or_else(invalid)?;
+        let (label, colour) = display.split_once(':').ok_or_else(invalid)?;
+        let status = status.trim();
+        if status.is_empty() || statuses.iter().any(|existing| existing.name == status) {
+            return Err(invalid());
+        }
+        statuses.push(PostStatus {
+            name: status.to_string(),
+            label: label.trim().to_string(),
+            colour: colour.trim().to_string(),
+        });
+    }
+    if statuses.is_empty() {
+        return Err(ConfigError { message: format!("{} environment variable has no statuses", name) });
+    }
+    Ok(statuses)
+}
+
+// parses "from>to,..." between the configured statuses
+fn transitions_from_env(name: &str, statuses: &[PostStatus]) -> Result<Vec<(String, String)>, ConfigError> {
+    let env_val = match get_optional_env(name) {
+        Some(env_val) => env_val,
+        None => return Ok(vec![]),
+    };
+    let mut transitions = vec![];
+    for entry in env_val.split(',').map(|entry| entry.trim()).filter(|entry| !entry.is_empty()) {
+        let invalid = || ConfigError { message: format!("{} environment variable has an invalid entry: {}", name, entry) };
+        let (from, to) = entry.split_once('>').ok_or_else(invalid)?;
+        let (from, to) = (from.trim(), to.trim());
+        if !statuses.iter().any(|status| status.name == from) || !statuses.iter().any(|status| status.name == to) {
+            return Err(invalid());
+        }
+        transitions.push((from.to_string(), to.to_string()));
+    }
+    Ok(transitions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size(" 20k "), Some(20 << 10));
+        assert_eq!(parse_size("20M"), Some(20 << 20));
+        assert_eq!(parse_size("2g"), Some(2 << 30));
+    }
+
+    #[test]
+    fn falls_back_to_the_default_statuses() {
+        let statuses = statuses_from_env("TEST_UNSET_POST_STATUSES", DEFAULT_POST_STATUSES).unwrap();
+        let colours: Vec<(&str, &str)> = statuses.iter().map(|status| (status.name.as_str(), status.colour.as_str())).collect();
+        assert_eq!(colours, vec![("done", "success"), ("abandoned", "danger")]);
+    }
+
+    #[test]
+    fn rejects_invalid_sizes() {
+        for size in ["", "M", "-1", "1.5M", "20MB", "twenty"] {
+            assert_eq!(parse_size(size), None, "{}", size);
+        }
+    }
+}
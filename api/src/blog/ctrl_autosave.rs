@@ -0,0 +1,22 @@
+use crate::blog::auth_middleware::get_user;
+use crate::blog::config::Config;
+use crate::blog::drafts::save_draft;
+use crate::blog::error::http_error;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct AutosaveData {
+    file: String,
+    content: String,
+}
+
+pub async fn ctrl_autosave(mut req: Request<Config>) -> tide::Result {
+    let AutosaveData { file, content } = req.body_json().await?;
+
+    if let Err(e) = save_draft(req.state(), get_user(&req).as_str(), file.as_str(), content) {
+        return Ok(http_error(e.status, e.message));
+    }
+
+    Ok(Response::builder(StatusCode::NoContent).build())
+}
@@ -0,0 +1,34 @@
+use crate::blog::auth_middleware::get_user;
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::status::change_status;
+use serde_json::json;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct ChangePostStatus {
+    file: String,
+    // removes the status if none
+    status: Option<String>,
+}
+
+pub async fn ctrl_change_post_status(mut req: Request<Config>) -> tide::Result {
+    let ChangePostStatus { file, status } = req.body_json().await?;
+
+    let _lock = match req.state().repo_lock.try_write("change status") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    match change_status(req.state(), file.as_str(), status.as_deref(), get_user(&req).as_str()) {
+        Ok(transition) => Ok(Response::builder(StatusCode::Ok)
+            .body(json!(transition))
+            .content_type(mime::JSON)
+            .build()),
+        Err(e) => Ok(http_error(e.status, e.message)),
+    }
+}
@@ -1,16 +1,41 @@
-use crate::blog::config::{Config, DEFAULT_BRANCH};
-use crate::blog::error::http_error;
+use crate::blog::config::{Config, HIGHLIGHT_THEME, REF_NAME};
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::generator::{new_tera, Generator};
+use crate::blog::signing::sign_commit_buffer;
+use crate::blog::utils::{get_staged_files, is_on_remote, CommitSummary};
+use comrak::plugins::syntect::SyntectAdapter;
 use git2::Repository;
+use serde::Serialize;
+use serde_json::json;
+use tide::http::mime;
 use tide::prelude::*;
 use tide::{Request, Response, StatusCode};
 
 #[derive(Debug, Deserialize)]
 struct Commit {
     message: String,
+    #[serde(default)]
+    force: bool,
+    #[serde(default)]
+    amend: bool,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidationResponse {
+    pub problems: Vec<String>,
 }
 
 pub async fn ctrl_commit(mut req: Request<Config>) -> tide::Result {
-    let Commit { message } = req.body_json().await?;
+    let Commit { message, force, amend, allow_empty } = req.body_json().await?;
+
+    let _lock = match req.state().repo_lock.try_write("commit") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
 
     let repo_path = req.state().get_input_path();
     let repo = match Repository::open(repo_path) {
@@ -27,6 +52,91 @@ pub async fn ctrl_commit(mut req: Request<Config>) -> tide::Result {
         }
     };
 
+    if req.state().require_signing && req.state().signing_key.is_none() {
+        return Ok(http_error(StatusCode::PreconditionFailed, "commit signing is required, but no SIGNING_KEY is configured"));
+    }
+
+    // only commit onto the default branch, HEAD may also be unborn (no commits yet)
+    let head_target = match repo.find_reference("HEAD") {
+        Ok(head) => head.symbolic_target().map(String::from),
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("unable to find HEAD: {}", e.message())));
+        }
+    };
+    match head_target {
+        Some(head_target) => {
+            if head_target != REF_NAME {
+                return Ok(http_error(StatusCode::Conflict, format!("HEAD points to {} instead of {}", head_target, REF_NAME)));
+            }
+        }
+        None => {
+            return Ok(http_error(StatusCode::Conflict, "HEAD is detached"));
+        }
+    }
+
+    let parent = match repo.find_reference(REF_NAME) {
+        Ok(reference) => match reference.peel_to_commit() {
+            Ok(commit) => Some(commit),
+            Err(e) => {
+                return Ok(http_error(StatusCode::InternalServerError, format!("unable to find last commit: {}", e.message())));
+            }
+        },
+        Err(_) => None,
+    };
+
+    if amend {
+        match &parent {
+            Some(parent) => {
+                match is_on_remote(&repo, parent.id()) {
+                    Some(false) => {}
+                    Some(true) => {
+                        return Ok(http_error(StatusCode::Conflict, "last commit has already been pushed"));
+                    }
+                    None => {
+                        return Ok(http_error(StatusCode::Conflict, "remote state is unknown, fetch first"));
+                    }
+                }
+            }
+            None => {
+                return Ok(http_error(StatusCode::Conflict, "no commit to amend"));
+            }
+        }
+    }
+
+    if req.state().validate_commits && !force {
+        let (sources, known_files) = match get_staged_files(&repo) {
+            Ok(staged_files) => staged_files,
+            Err(e) => {
+                return Ok(http_error(StatusCode::InternalServerError, format!("unable to read index: {}", e.message())));
+            }
+        };
+
+        let tera = match new_tera(req.state()) {
+            Ok(t) => t,
+            Err(e) => {
+                return Ok(http_error(StatusCode::InternalServerError, format!("unable to generate config: {}", e)));
+            }
+        };
+
+        let adapter = SyntectAdapter::new(Some(HIGHLIGHT_THEME));
+        let mut generator = Generator::new(
+            &tera,
+            req.state().get_input_path(),
+            req.state().get_output_path(),
+            Some(&adapter),
+        );
+        generator.set_statuses(&req.state().statuses, req.state().strict_statuses);
+        generator.log_to_buffer();
+
+        let problems = generator.validate(&sources, &known_files);
+        if !problems.is_empty() {
+            return Ok(Response::builder(StatusCode::UnprocessableEntity)
+                .body(json!(ValidationResponse { problems }))
+                .content_type(mime::JSON)
+                .build());
+        }
+    }
+
     let mut index = repo.index().unwrap();
     let tree = match index.write_tree() {
         Ok(tree) => tree,
@@ -35,19 +145,116 @@ pub async fn ctrl_commit(mut req: Request<Config>) -> tide::Result {
         }
     };
 
-    let branch = repo.revparse_single(DEFAULT_BRANCH).unwrap();
-    let commit = branch.as_commit().unwrap();
+    let tree = match repo.find_tree(tree) {
+        Ok(tree) => tree,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("could not find tree: {}", e)));
+        }
+    };
 
-    if let Err(e) = repo.commit(
-        Some("HEAD"),
-        &signature,
-        &signature,
-        message.as_str(),
-        &repo.find_tree(tree).unwrap(),
-        &[&commit],
-    ) {
-        return Ok(http_error(StatusCode::InternalServerError, format!("unable to push to remote: {}", e.message())));
+    // the tree to compare against, when amending it is the one before the amended commit
+    let base_tree_id = match &parent {
+        Some(parent) if amend => match parent.parent(0) {
+            Ok(grand_parent) => Some(grand_parent.tree_id()),
+            Err(_) => None,
+        },
+        Some(parent) => Some(parent.tree_id()),
+        None => None,
+    };
+    let is_empty = match base_tree_id {
+        Some(base_tree_id) => base_tree_id == tree.id(),
+        None => tree.is_empty(),
+    };
+    if is_empty && !allow_empty {
+        return Ok(http_error(StatusCode::UnprocessableEntity, "nothing to commit"));
     }
 
-    Ok(Response::builder(StatusCode::NoContent).build())
-}
\ No newline at end of file
+    // amending keeps the original author and parents
+    let author = match &parent {
+        Some(parent) if amend => parent.author().to_owned(),
+        _ => signature.clone(),
+    };
+    let parents: Vec<git2::Commit> = match &parent {
+        Some(parent) if amend => parent.parents().collect(),
+        Some(parent) => vec![parent.clone()],
+        None => vec![],
+    };
+    let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+
+    let buffer = match repo.commit_create_buffer(&author, &signature, message.as_str(), &tree, &parent_refs) {
+        Ok(buffer) => buffer,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("unable to create commit: {}", e.message())));
+        }
+    };
+    let buffer = match buffer.as_str() {
+        Some(buffer) => buffer.to_string(),
+        None => {
+            return Ok(http_error(StatusCode::InternalServerError, "commit is not valid utf-8"));
+        }
+    };
+
+    let commit_signature = match sign_commit_buffer(req.state(), buffer.as_str()) {
+        Ok(commit_signature) => commit_signature,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("unable to sign commit: {}", e.message)));
+        }
+    };
+
+    let commit_result = match commit_signature {
+        Some(commit_signature) => {
+            match repo.commit_signed(buffer.as_str(), commit_signature.as_str(), None) {
+                Ok(commit_id) => {
+                    // commit_signed doesn't move the branch, so update it unless it moved meanwhile
+                    let log_message = format!(
+                        "{}: {}",
+                        if amend { "commit (amend)" } else { "commit" },
+                        message.lines().next().unwrap_or("")
+                    );
+                    let reference_result = match &parent {
+                        Some(parent) => repo.reference_matching(REF_NAME, commit_id, true, parent.id(), log_message.as_str()),
+                        None => repo.reference(REF_NAME, commit_id, false, log_message.as_str()),
+                    };
+                    reference_result.map(|_| commit_id)
+                }
+                Err(e) => Err(e),
+            }
+        }
+        None => match &parent {
+            Some(parent) if amend => parent.amend(
+                Some(REF_NAME),
+                None,
+                Some(&signature),
+                None,
+                Some(message.as_str()),
+                Some(&tree),
+            ),
+            _ => repo.commit(
+                Some(REF_NAME),
+                &signature,
+                &signature,
+                message.as_str(),
+                &tree,
+                &parent_refs,
+            ),
+        },
+    };
+    let commit_id = match commit_result {
+        Ok(commit_id) => commit_id,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("unable to commit: {}", e.message())));
+        }
+    };
+
+    let commit = match repo.find_commit(commit_id) {
+        Ok(commit) => commit,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("unable to find new commit: {}", e.message())));
+        }
+    };
+
+    Ok(Response::builder(StatusCode::Created)
+        .body(json!(CommitSummary::from_commit(&commit)))
+        .content_type(mime::JSON)
+        .build())
+}
@@ -1,7 +1,8 @@
+use crate::blog::auth_middleware::get_user;
 use crate::blog::config::Config;
-use crate::blog::error::http_error;
-use std::fs;
-use std::path::Path;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::trash::move_to_trash;
+use crate::blog::utils::join_relative;
 use tide::prelude::*;
 use tide::{Request, Response, StatusCode};
 
@@ -13,18 +14,31 @@ struct DeleteFile {
 pub async fn ctrl_delete(mut req: Request<Config>) -> tide::Result {
     let DeleteFile { file } = req.body_json().await?;
 
-    let path_str = format!("{}/{}", req.state().get_input_path().to_string_lossy(), file);
-    let path = Path::new(path_str.as_str());
+    let _lock = match req.state().repo_lock.try_write("delete") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    // only paths below the posts folder
+    let path = match join_relative(&req.state().get_input_path(), file.as_str()) {
+        Some(path) if path != req.state().get_input_path() => path,
+        _ => {
+            return Ok(http_error(StatusCode::BadRequest, format!("invalid path: {}", file)));
+        }
+    };
     if !path.exists() {
         return Ok(Response::builder(StatusCode::NotFound).build());
     }
 
-    if let Err(e) = if path.is_dir() {
-        fs::remove_dir(path)
-    } else {
-        fs::remove_file(path)
-    } {
-        return Ok(http_error(StatusCode::InternalServerError, format!("unable to remove: {}", e)));
+    // like before, only empty folders can be deleted
+    if path.is_dir() && path.read_dir().map(|mut entries| entries.next().is_some()).unwrap_or(false) {
+        return Ok(http_error(StatusCode::Conflict, "folder is not empty"));
+    }
+
+    if let Err(e) = move_to_trash(req.state(), file.as_str(), get_user(&req).as_str()) {
+        return Ok(http_error(e.status, e.message));
     }
 
     Ok(Response::builder(StatusCode::NoContent).build())
@@ -0,0 +1,75 @@
+use crate::blog::assets::get_assets;
+use crate::blog::auth_middleware::get_user;
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::trash::{move_to_trash, TrashItem};
+use serde::Serialize;
+use serde_json::json;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct DeleteAssets {
+    // all orphans are deleted if no files are given
+    files: Option<Vec<String>>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct DeleteAssetsResponse {
+    dry_run: bool,
+    files: Vec<String>,
+    trash: Vec<TrashItem>,
+}
+
+// moves orphaned assets into the trash, assets still referenced by a post are refused
+pub async fn ctrl_delete_assets(mut req: Request<Config>) -> tide::Result {
+    let DeleteAssets { files, dry_run } = req.body_json().await?;
+
+    let _lock = match req.state().repo_lock.try_write("delete assets") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    let assets = match get_assets(req.state()) {
+        Ok(assets) => assets,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+    let orphans: Vec<String> = assets.into_iter().filter(|asset| asset.orphan).map(|asset| asset.name).collect();
+
+    let files = match files {
+        Some(files) => files,
+        None => orphans.clone(),
+    };
+    let not_orphans: Vec<&String> = files.iter().filter(|file| !orphans.contains(file)).collect();
+    if !not_orphans.is_empty() {
+        return Ok(http_error(
+            StatusCode::Conflict,
+            format!("not orphaned: {}", not_orphans.iter().map(|file| file.as_str()).collect::<Vec<&str>>().join(", ")),
+        ));
+    }
+
+    let mut trash = vec![];
+    if !dry_run {
+        let user = get_user(&req);
+        for file in files.iter() {
+            match move_to_trash(req.state(), file.as_str(), user.as_str()) {
+                Ok(item) => trash.push(item),
+                Err(e) => {
+                    return Ok(http_error(e.status, e.message));
+                }
+            }
+        }
+    }
+
+    Ok(Response::builder(StatusCode::Ok)
+        .body(json!(DeleteAssetsResponse { dry_run, files, trash }))
+        .content_type(mime::JSON)
+        .build())
+}
@@ -0,0 +1,62 @@
+use crate::blog::auth_middleware::get_user;
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::posts::PostPaths;
+use crate::blog::trash::{move_to_trash, TrashItem};
+use serde::Serialize;
+use serde_json::json;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct DeletePost {
+    file: String,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct DeleteResponse {
+    dry_run: bool,
+    files: Vec<String>,
+    trash: Vec<TrashItem>,
+}
+
+// moves a post together with its asset folder into the trash
+pub async fn ctrl_delete_post(mut req: Request<Config>) -> tide::Result {
+    let DeletePost { file, dry_run } = req.body_json().await?;
+
+    let _lock = match req.state().repo_lock.try_write("delete post") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    let post = match PostPaths::find(req.state(), file.as_str()) {
+        Ok(post) => post,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+    let files = post.list_files(req.state());
+
+    let mut trash = vec![];
+    if !dry_run {
+        let user = get_user(&req);
+        for path in std::iter::once(&post.file).chain(post.asset_dir.iter()) {
+            match move_to_trash(req.state(), path.as_str(), user.as_str()) {
+                Ok(item) => trash.push(item),
+                Err(e) => {
+                    return Ok(http_error(e.status, e.message));
+                }
+            }
+        }
+    }
+
+    Ok(Response::builder(StatusCode::Ok)
+        .body(json!(DeleteResponse { dry_run, files, trash }))
+        .content_type(mime::JSON)
+        .build())
+}
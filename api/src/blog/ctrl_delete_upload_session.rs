@@ -0,0 +1,11 @@
+use crate::blog::config::Config;
+use crate::blog::error::http_error;
+use crate::blog::upload_sessions::delete_session;
+use tide::{Request, Response, StatusCode};
+
+pub async fn ctrl_delete_upload_session(req: Request<Config>) -> tide::Result {
+    match delete_session(req.state(), req.param("id")?) {
+        Ok(()) => Ok(Response::builder(StatusCode::NoContent).build()),
+        Err(e) => Ok(http_error(e.status, e.message)),
+    }
+}
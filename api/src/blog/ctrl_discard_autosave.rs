@@ -0,0 +1,21 @@
+use crate::blog::auth_middleware::get_user;
+use crate::blog::config::Config;
+use crate::blog::drafts::discard_draft;
+use crate::blog::error::http_error;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct DiscardData {
+    file: String,
+}
+
+pub async fn ctrl_discard_autosave(mut req: Request<Config>) -> tide::Result {
+    let DiscardData { file } = req.body_json().await?;
+
+    match discard_draft(req.state(), get_user(&req).as_str(), file.as_str()) {
+        Ok(true) => Ok(Response::builder(StatusCode::NoContent).build()),
+        Ok(false) => Ok(Response::builder(StatusCode::NotFound).build()),
+        Err(e) => Ok(http_error(e.status, e.message)),
+    }
+}
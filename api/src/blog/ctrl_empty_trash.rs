@@ -0,0 +1,25 @@
+use crate::blog::config::Config;
+use crate::blog::error::http_error;
+use crate::blog::trash::empty_trash;
+use serde_json::json;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct EmptyData {
+    // all items are removed if no ids are given
+    ids: Option<Vec<String>>,
+}
+
+pub async fn ctrl_empty_trash(mut req: Request<Config>) -> tide::Result {
+    let EmptyData { ids } = req.body_json().await?;
+
+    match empty_trash(req.state(), ids) {
+        Ok(removed) => Ok(Response::builder(StatusCode::Ok)
+            .body(json!({ "removed": removed }))
+            .content_type(mime::JSON)
+            .build()),
+        Err(e) => Ok(http_error(e.status, e.message)),
+    }
+}
@@ -0,0 +1,32 @@
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::upload_sessions::finalize_session;
+use serde_json::json;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct FinalizeData {
+    // hex encoded sha256 of the whole file
+    sha256: String,
+}
+
+pub async fn ctrl_finalize_upload_session(mut req: Request<Config>) -> tide::Result {
+    let FinalizeData { sha256 } = req.body_json().await?;
+
+    let _lock = match req.state().repo_lock.try_write("upload") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    match finalize_session(req.state(), req.param("id")?, sha256.as_str()) {
+        Ok(uploaded_file) => Ok(Response::builder(StatusCode::Created)
+            .body(json!(uploaded_file))
+            .content_type(mime::JSON)
+            .build()),
+        Err(e) => Ok(http_error(e.status, e.message)),
+    }
+}
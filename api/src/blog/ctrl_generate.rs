@@ -1,32 +1,21 @@
-use crate::blog::config::{Config, HIGHLIGHT_THEME};
+use crate::blog::config::Config;
+use crate::blog::ctrl_new_job::NewJobResponse;
 use crate::blog::error::http_error;
-use crate::blog::generator::Generator;
-use comrak::plugins::syntect::SyntectAdapter;
-use tera::Tera;
+use serde_json::json;
+use tide::http::mime;
 use tide::{Request, Response, StatusCode};
 
+// queues the job and returns its id, the progress is followed through /api/jobs/:id/events
 pub async fn ctrl_generate(req: Request<Config>) -> tide::Result {
-    let tera = match Tera::new(format!("{}/templates/*.html", req.state().working_path).as_str()) {
-        Ok(t) => t,
+    let id = match req.state().jobs.enqueue(req.state(), "generate") {
+        Ok(id) => id,
         Err(e) => {
-            return Ok(http_error(StatusCode::InternalServerError, format!("unable to generate config: {}", e)));
+            return Ok(http_error(e.status, e.message));
         }
     };
 
-    let adapter = SyntectAdapter::new(Some(HIGHLIGHT_THEME));
-    let mut generator = Generator::new(
-        &tera,
-        req.state().get_input_path(),
-        req.state().get_output_path(),
-        Some(&adapter),
-    );
-    generator.log_to_buffer();
-
-    if let Err(e) = generator.generate() {
-        return Ok(http_error(StatusCode::InternalServerError, format!("unable to generate file: {}", e.message)));
-    }
-
-    Ok(Response::builder(StatusCode::Ok)
-        .body(generator.get_log_result())
+    Ok(Response::builder(StatusCode::Accepted)
+        .body(json!(NewJobResponse { id }))
+        .content_type(mime::JSON)
         .build())
-}
\ No newline at end of file
+}
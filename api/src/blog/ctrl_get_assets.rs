@@ -0,0 +1,39 @@
+use crate::blog::assets::get_assets;
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use serde_json::json;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct AssetsQuery {
+    #[serde(default)]
+    orphans: bool,
+}
+
+pub async fn ctrl_get_assets(req: Request<Config>) -> tide::Result {
+    let AssetsQuery { orphans } = req.query()?;
+
+    let _lock = match req.state().repo_lock.try_read() {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    let mut assets = match get_assets(req.state()) {
+        Ok(assets) => assets,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+    if orphans {
+        assets.retain(|asset| asset.orphan);
+    }
+
+    Ok(Response::builder(StatusCode::Ok)
+        .body(json!(assets))
+        .content_type(mime::JSON)
+        .build())
+}
@@ -0,0 +1,27 @@
+use crate::blog::auth_middleware::get_user;
+use crate::blog::config::Config;
+use crate::blog::drafts::get_draft;
+use crate::blog::error::http_error;
+use serde_json::json;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct AutosaveQuery {
+    file: String,
+}
+
+// returns the draft including its content to restore it in the editor
+pub async fn ctrl_get_autosave(req: Request<Config>) -> tide::Result {
+    let AutosaveQuery { file } = req.query()?;
+
+    match get_draft(req.state(), get_user(&req).as_str(), file.as_str()) {
+        Ok(Some(draft)) => Ok(Response::builder(StatusCode::Ok)
+            .body(json!(draft))
+            .content_type(mime::JSON)
+            .build()),
+        Ok(None) => Ok(Response::builder(StatusCode::NotFound).build()),
+        Err(e) => Ok(http_error(e.status, e.message)),
+    }
+}
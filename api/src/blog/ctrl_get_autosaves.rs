@@ -0,0 +1,21 @@
+use crate::blog::auth_middleware::get_user;
+use crate::blog::config::Config;
+use crate::blog::drafts::list_drafts;
+use crate::blog::error::http_error;
+use serde_json::json;
+use tide::http::mime;
+use tide::{Request, Response, StatusCode};
+
+pub async fn ctrl_get_autosaves(req: Request<Config>) -> tide::Result {
+    let drafts = match list_drafts(req.state(), get_user(&req).as_str()) {
+        Ok(drafts) => drafts,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+
+    Ok(Response::builder(StatusCode::Ok)
+        .body(json!(drafts))
+        .content_type(mime::JSON)
+        .build())
+}
@@ -1,4 +1,4 @@
-use crate::blog::error::http_error;
+use crate::blog::error::{http_error, http_retry_error};
 use crate::blog::utils::{get_changes, get_diffs, Change, Diff};
 use git2::Repository;
 use serde::Serialize;
@@ -15,6 +15,13 @@ pub struct ChangeResponse {
 }
 
 pub async fn ctrl_get_changes(req: Request<Config>) -> tide::Result {
+    let _lock = match req.state().repo_lock.try_read() {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
     let path = req.state().get_input_path();
     let repo = match Repository::open(path) {
         Ok(repo) => repo,
@@ -0,0 +1,20 @@
+use crate::blog::config::Config;
+use crate::blog::error::http_error;
+use crate::blog::publisher::get_deployments;
+use serde_json::json;
+use tide::http::mime;
+use tide::{Request, Response, StatusCode};
+
+pub async fn ctrl_get_deployments(req: Request<Config>) -> tide::Result {
+    let deployments = match get_deployments(req.state()) {
+        Ok(deployments) => deployments,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+
+    Ok(Response::builder(StatusCode::Ok)
+        .body(json!(deployments))
+        .content_type(mime::JSON)
+        .build())
+}
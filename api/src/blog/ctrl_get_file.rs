@@ -0,0 +1,51 @@
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::utils::store_merge_base;
+use std::fs;
+use std::path::Path;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct FileQuery {
+    path: String,
+}
+
+pub async fn ctrl_get_file(req: Request<Config>) -> tide::Result {
+    let FileQuery { path } = req.query()?;
+
+    let _lock = match req.state().repo_lock.try_read() {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    let path_str = format!("{}/{}", req.state().get_input_path().to_string_lossy(), path);
+    let file_path = Path::new(path_str.as_str());
+    if !file_path.is_file() {
+        return Ok(Response::builder(StatusCode::NotFound).build());
+    }
+
+    let content = match fs::read(file_path) {
+        Ok(content) => content,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("unable to read: {}", e)));
+        }
+    };
+
+    // keep the content, so it can serve as merge base when saving
+    let hash = match store_merge_base(req.state(), &content) {
+        Ok(hash) => hash,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("unable to keep merge base: {}", e)));
+        }
+    };
+
+    Ok(Response::builder(StatusCode::Ok)
+        .header("ETag", format!("\"{}\"", hash))
+        .body(content)
+        .content_type(mime::PLAIN)
+        .build())
+}
@@ -1,3 +1,4 @@
+use crate::blog::error::http_retry_error;
 use crate::blog::utils::{find_files, get_entries, Content};
 use serde_json::json;
 use tide::http::mime;
@@ -6,6 +7,13 @@ use tide::{Request, Response, StatusCode};
 use crate::Config;
 
 pub async fn ctrl_get_files(req: Request<Config>) -> tide::Result {
+    let _lock = match req.state().repo_lock.try_read() {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
     let path = req.state().get_input_path();
     let mut files = find_files(&path, None);
     let (files, unknown_files) = get_entries(&mut files);
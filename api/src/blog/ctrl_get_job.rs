@@ -0,0 +1,21 @@
+use crate::blog::config::Config;
+use serde_json::json;
+use tide::http::mime;
+use tide::{Request, Response, StatusCode};
+
+pub async fn ctrl_get_job(req: Request<Config>) -> tide::Result {
+    let id: u64 = match req.param("id")?.parse() {
+        Ok(id) => id,
+        Err(_) => {
+            return Ok(Response::builder(StatusCode::BadRequest).build());
+        }
+    };
+
+    match req.state().jobs.get(id) {
+        Some(job) => Ok(Response::builder(StatusCode::Ok)
+            .body(json!(job))
+            .content_type(mime::JSON)
+            .build()),
+        None => Ok(Response::builder(StatusCode::NotFound).build()),
+    }
+}
@@ -0,0 +1,36 @@
+use crate::blog::config::Config;
+use serde_json::json;
+use std::time::Duration;
+use tide::sse::Sender;
+use tide::Request;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// streams the log of a job as "log" events, followed by a final "done" event with the job,
+// all event data is json encoded since log chunks span multiple lines
+pub async fn ctrl_get_job_events(req: Request<Config>, sender: Sender) -> tide::Result<()> {
+    let id: u64 = req.param("id")?.parse()?;
+
+    let mut log_pos: usize = 0;
+    loop {
+        let job = match req.state().jobs.get(id) {
+            Some(job) => job,
+            None => {
+                sender.send("error", json!("job not found").to_string(), None).await?;
+                return Ok(());
+            }
+        };
+
+        if job.log.len() > log_pos {
+            sender.send("log", json!(&job.log[log_pos..]).to_string(), None).await?;
+            log_pos = job.log.len();
+        }
+
+        if job.is_finished() {
+            sender.send("done", json!(job).to_string(), None).await?;
+            return Ok(());
+        }
+
+        async_std::task::sleep(POLL_INTERVAL).await;
+    }
+}
@@ -0,0 +1,11 @@
+use crate::blog::config::Config;
+use serde_json::json;
+use tide::http::mime;
+use tide::{Request, Response, StatusCode};
+
+pub async fn ctrl_get_jobs(req: Request<Config>) -> tide::Result {
+    Ok(Response::builder(StatusCode::Ok)
+        .body(json!(req.state().jobs.list()))
+        .content_type(mime::JSON)
+        .build())
+}
@@ -0,0 +1,25 @@
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::posts::{list_posts, PostsQuery};
+use serde_json::json;
+use tide::http::mime;
+use tide::{Request, Response, StatusCode};
+
+pub async fn ctrl_get_posts(req: Request<Config>) -> tide::Result {
+    let query: PostsQuery = req.query()?;
+
+    let _lock = match req.state().repo_lock.try_read() {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    match list_posts(req.state(), &query) {
+        Ok(posts) => Ok(Response::builder(StatusCode::Ok)
+            .body(json!(posts))
+            .content_type(mime::JSON)
+            .build()),
+        Err(e) => Ok(http_error(e.status, e.message)),
+    }
+}
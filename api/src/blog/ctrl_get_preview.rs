@@ -1,8 +1,7 @@
 use crate::blog::config::{Config, HIGHLIGHT_THEME};
-use crate::blog::error::http_error;
-use crate::blog::generator::{Generator, Post};
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::generator::{new_tera, Generator, Post};
 use comrak::plugins::syntect::SyntectAdapter;
-use tera::Tera;
 use tide::http::mime;
 use tide::prelude::*;
 use tide::{Request, Response, StatusCode};
@@ -15,7 +14,14 @@ struct PreviewData {
 pub async fn ctrl_get_preview(mut req: Request<Config>) -> tide::Result {
     let PreviewData { content } = req.body_json().await?;
 
-    let tera = match Tera::new(format!("{}/templates/*.html", req.state().working_path).as_str()) {
+    let _lock = match req.state().repo_lock.try_read() {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    let tera = match new_tera(req.state()) {
         Ok(t) => t,
         Err(e) => {
             return Ok(http_error(StatusCode::InternalServerError, format!("unable to generate config: {:?}", e)));
@@ -29,6 +35,7 @@ pub async fn ctrl_get_preview(mut req: Request<Config>) -> tide::Result {
         req.state().get_output_path(),
         Some(&adapter),
     );
+    generator.set_statuses(&req.state().statuses, req.state().strict_statuses);
     let mut content_mut = content.clone();
 
     let post = match generator.new_post(String::from("preview"), &mut content_mut) {
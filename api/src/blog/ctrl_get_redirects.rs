@@ -0,0 +1,20 @@
+use crate::blog::config::Config;
+use crate::blog::error::http_error;
+use crate::blog::redirects::get_redirects;
+use serde_json::json;
+use tide::http::mime;
+use tide::{Request, Response, StatusCode};
+
+pub async fn ctrl_get_redirects(req: Request<Config>) -> tide::Result {
+    let redirects = match get_redirects(&req.state().get_input_path()) {
+        Ok(redirects) => redirects,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, e.message));
+        }
+    };
+
+    Ok(Response::builder(StatusCode::Ok)
+        .body(json!(redirects))
+        .content_type(mime::JSON)
+        .build())
+}
@@ -0,0 +1,48 @@
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use git2::Repository;
+use serde::Serialize;
+use serde_json::json;
+use tide::http::mime;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Serialize)]
+pub struct Stash {
+    pub index: usize,
+    pub message: String,
+    pub id: String,
+}
+
+pub async fn ctrl_get_stashes(req: Request<Config>) -> tide::Result {
+    let _lock = match req.state().repo_lock.try_read() {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    let repo_path = req.state().get_input_path();
+    let mut repo = match Repository::open(repo_path) {
+        Ok(repo) => repo,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("failed to open: {}", e.message())));
+        }
+    };
+
+    let mut stashes: Vec<Stash> = vec![];
+    if let Err(e) = repo.stash_foreach(|index, message, id| {
+        stashes.push(Stash {
+            index,
+            message: message.to_string(),
+            id: id.to_string(),
+        });
+        true
+    }) {
+        return Ok(http_error(StatusCode::InternalServerError, format!("unable to list stashes: {}", e.message())));
+    }
+
+    Ok(Response::builder(StatusCode::Ok)
+        .body(json!(stashes))
+        .content_type(mime::JSON)
+        .build())
+}
@@ -0,0 +1,24 @@
+use crate::blog::config::Config;
+use crate::blog::error::http_error;
+use crate::blog::status::get_status_transitions;
+use serde_json::json;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct StatusHistoryQuery {
+    file: Option<String>,
+}
+
+pub async fn ctrl_get_status_history(req: Request<Config>) -> tide::Result {
+    let StatusHistoryQuery { file } = req.query()?;
+
+    match get_status_transitions(req.state(), file.as_deref()) {
+        Ok(transitions) => Ok(Response::builder(StatusCode::Ok)
+            .body(json!(transitions))
+            .content_type(mime::JSON)
+            .build()),
+        Err(e) => Ok(http_error(e.status, e.message)),
+    }
+}
@@ -0,0 +1,15 @@
+use crate::blog::config::Config;
+use serde_json::json;
+use tide::http::mime;
+use tide::{Request, Response, StatusCode};
+
+pub async fn ctrl_get_statuses(req: Request<Config>) -> tide::Result {
+    Ok(Response::builder(StatusCode::Ok)
+        .body(json!({
+            "statuses": req.state().statuses,
+            "strict": req.state().strict_statuses,
+            "transitions": req.state().status_transitions,
+        }))
+        .content_type(mime::JSON)
+        .build())
+}
@@ -0,0 +1,108 @@
+use crate::blog::config::{Config, DEFAULT_BRANCH, TRACKING_REF_NAME};
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::utils::{fetch_remote, get_commits_between, CommitSummary};
+use git2::Repository;
+use serde::Serialize;
+use serde_json::json;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct SyncQuery {
+    fetch: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncStatusResponse {
+    pub fetched: bool,
+    pub ahead: usize,
+    pub behind: usize,
+    pub unpushed: Vec<CommitSummary>,
+    pub unpulled: Vec<CommitSummary>,
+}
+
+pub async fn ctrl_get_sync_status(req: Request<Config>) -> tide::Result {
+    let SyncQuery { fetch } = req.query()?;
+    let fetch = fetch.unwrap_or(true);
+
+    // fetching updates refs, otherwise only reading
+    let _write_lock;
+    let _read_lock;
+    if fetch {
+        _write_lock = match req.state().repo_lock.try_write("fetch") {
+            Ok(lock) => lock,
+            Err(e) => {
+                return Ok(http_retry_error(e.status, e.message));
+            }
+        };
+    } else {
+        _read_lock = match req.state().repo_lock.try_read() {
+            Ok(lock) => lock,
+            Err(e) => {
+                return Ok(http_retry_error(e.status, e.message));
+            }
+        };
+    }
+
+    let repo_path = req.state().get_input_path();
+    let repo = match Repository::open(repo_path) {
+        Ok(repo) => repo,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("failed to open: {}", e.message())));
+        }
+    };
+
+    // fetch remote, otherwise rely on the remote tracking branch of the last fetch or push
+    if fetch {
+        if let Err(e) = fetch_remote(req.state(), &repo) {
+            return Ok(http_error(StatusCode::InternalServerError, format!("unable to fetch from remote: {}", e.message())));
+        }
+    }
+
+    let local_oid = match repo.refname_to_id(format!("refs/heads/{}", DEFAULT_BRANCH).as_str()) {
+        Ok(local_oid) => local_oid,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("unable to find local branch: {}", e.message())));
+        }
+    };
+    let remote_oid = match repo.refname_to_id(TRACKING_REF_NAME) {
+        Ok(remote_oid) => remote_oid,
+        Err(e) => {
+            return Ok(http_error(StatusCode::Conflict, format!("unable to find {}, fetch first: {}", TRACKING_REF_NAME, e.message())));
+        }
+    };
+
+    let (ahead, behind) = match repo.graph_ahead_behind(local_oid, remote_oid) {
+        Ok(ahead_behind) => ahead_behind,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("unable to compare branches: {}", e.message())));
+        }
+    };
+
+    let unpushed = match get_commits_between(&repo, local_oid, remote_oid) {
+        Ok(unpushed) => unpushed,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("unable to list unpushed commits: {}", e.message())));
+        }
+    };
+    let unpulled = match get_commits_between(&repo, remote_oid, local_oid) {
+        Ok(unpulled) => unpulled,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("unable to list unpulled commits: {}", e.message())));
+        }
+    };
+
+    let json_payload = json!(SyncStatusResponse {
+        fetched: fetch,
+        ahead,
+        behind,
+        unpushed,
+        unpulled,
+    });
+
+    Ok(Response::builder(StatusCode::Ok)
+        .body(json_payload)
+        .content_type(mime::JSON)
+        .build())
+}
@@ -0,0 +1,20 @@
+use crate::blog::config::Config;
+use crate::blog::error::http_error;
+use crate::blog::trash::get_trash;
+use serde_json::json;
+use tide::http::mime;
+use tide::{Request, Response, StatusCode};
+
+pub async fn ctrl_get_trash(req: Request<Config>) -> tide::Result {
+    let items = match get_trash(req.state()) {
+        Ok(items) => items,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+
+    Ok(Response::builder(StatusCode::Ok)
+        .body(json!(items))
+        .content_type(mime::JSON)
+        .build())
+}
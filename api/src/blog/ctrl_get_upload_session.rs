@@ -0,0 +1,17 @@
+use crate::blog::config::Config;
+use crate::blog::error::http_error;
+use crate::blog::upload_sessions::get_session;
+use serde_json::json;
+use tide::http::mime;
+use tide::{Request, Response, StatusCode};
+
+// returns the offset to resume an interrupted upload from
+pub async fn ctrl_get_upload_session(req: Request<Config>) -> tide::Result {
+    match get_session(req.state(), req.param("id")?) {
+        Ok(session) => Ok(Response::builder(StatusCode::Ok)
+            .body(json!(session))
+            .content_type(mime::JSON)
+            .build()),
+        Err(e) => Ok(http_error(e.status, e.message)),
+    }
+}
@@ -0,0 +1,90 @@
+use crate::blog::config::{Config, REF_NAME};
+use crate::blog::error::http_error;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+// signature headers carrying a hex encoded hmac-sha256 of the body
+const SIGNATURE_HEADERS: &[(&str, &str)] = &[
+    ("X-Hub-Signature-256", "sha256="),
+    ("X-Gitea-Signature", ""),
+    ("X-Gogs-Signature", ""),
+];
+// gitlab sends the secret itself instead of a signature
+const TOKEN_HEADER: &str = "X-Gitlab-Token";
+
+#[derive(Debug, Deserialize)]
+struct PushPayload {
+    #[serde(rename = "ref")]
+    reference: String,
+}
+
+pub async fn ctrl_hook_push(mut req: Request<Config>) -> tide::Result {
+    let secret = match &req.state().webhook_secret {
+        Some(secret) => secret.clone(),
+        None => {
+            return Ok(Response::builder(StatusCode::NotFound).build());
+        }
+    };
+
+    let body = req.body_bytes().await?;
+    if !is_authenticated(&req, secret.as_bytes(), &body) {
+        return Ok(http_error(StatusCode::Unauthorized, "invalid signature"));
+    }
+
+    let PushPayload { reference } = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            return Ok(http_error(StatusCode::BadRequest, format!("invalid payload: {}", e)));
+        }
+    };
+    if reference != REF_NAME {
+        return Ok(Response::builder(StatusCode::Ok)
+            .body(format!("ignoring push to {}", reference))
+            .build());
+    }
+
+    // forges time out quickly, so pull and generate in the background
+    let id = match req.state().jobs.enqueue(req.state(), "hook_push") {
+        Ok(id) => id,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+
+    Ok(Response::builder(StatusCode::Accepted)
+        .body(json!({ "id": id }))
+        .build())
+}
+
+fn is_authenticated(req: &Request<Config>, secret: &[u8], body: &[u8]) -> bool {
+    for (header_name, prefix) in SIGNATURE_HEADERS {
+        if let Some(header) = req.header(*header_name) {
+            let value = header.last().as_str();
+            let signature = match value.strip_prefix(prefix).map(hex::decode) {
+                Some(Ok(signature)) => signature,
+                _ => return false,
+            };
+            let mut mac = match Hmac::<Sha256>::new_from_slice(secret) {
+                Ok(mac) => mac,
+                Err(_) => return false,
+            };
+            mac.update(body);
+            return mac.verify_slice(&signature).is_ok();
+        }
+    }
+
+    if let Some(header) = req.header(TOKEN_HEADER) {
+        return constant_time_eq(header.last().as_str().as_bytes(), secret);
+    }
+
+    false
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
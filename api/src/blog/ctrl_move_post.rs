@@ -0,0 +1,95 @@
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::links::{apply_link_rewrites, plan_link_rewrites, LinkRewrite};
+use crate::blog::posts::{PathMove, PostPaths};
+use serde::Serialize;
+use serde_json::json;
+use std::fs;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct MovePost {
+    file: String,
+    new_file: String,
+    #[serde(default)]
+    update_links: bool,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct MoveResponse {
+    dry_run: bool,
+    moves: Vec<PathMove>,
+    rewrites: Vec<LinkRewrite>,
+}
+
+// renames a post together with its asset folder
+pub async fn ctrl_move_post(mut req: Request<Config>) -> tide::Result {
+    let MovePost { file, new_file, update_links, dry_run } = req.body_json().await?;
+
+    let _lock = match req.state().repo_lock.try_write("move post") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    let post = match PostPaths::find(req.state(), file.as_str()) {
+        Ok(post) => post,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+    let moves = match post.plan_move(req.state(), new_file.as_str()) {
+        Ok(moves) => moves,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+
+    let new_asset_dir = new_file.trim_end_matches(".md");
+    let rewrites = if update_links {
+        let mut renames = vec![(file.clone(), new_file.clone())];
+        if let Some(asset_dir) = &post.asset_dir {
+            renames.push((asset_dir.clone(), new_asset_dir.to_string()));
+        }
+        match plan_link_rewrites(req.state(), &renames) {
+            Ok(rewrites) => rewrites,
+            Err(e) => {
+                return Ok(http_error(e.status, e.message));
+            }
+        }
+    } else {
+        vec![]
+    };
+
+    if !dry_run {
+        let input_path = req.state().get_input_path();
+        if let Err(e) = fs::rename(input_path.join(file.as_str()), input_path.join(new_file.as_str())) {
+            return Ok(http_error(StatusCode::InternalServerError, format!("unable to move post: {}", e)));
+        }
+        if let Some(asset_dir) = &post.asset_dir {
+            if let Err(e) = fs::rename(input_path.join(asset_dir), input_path.join(new_asset_dir)) {
+                // keep the post and its assets together
+                let _ = fs::rename(input_path.join(new_file.as_str()), input_path.join(file.as_str()));
+                return Ok(http_error(StatusCode::InternalServerError, format!("unable to move assets: {}", e)));
+            }
+        }
+        if let Err(e) = apply_link_rewrites(req.state(), &rewrites) {
+            // undo the move, so posts and links stay consistent
+            if let Some(asset_dir) = &post.asset_dir {
+                let _ = fs::rename(input_path.join(new_asset_dir), input_path.join(asset_dir));
+            }
+            let _ = fs::rename(input_path.join(new_file.as_str()), input_path.join(file.as_str()));
+            return Ok(http_error(e.status, e.message));
+        }
+    }
+
+    Ok(Response::builder(StatusCode::Ok)
+        .body(json!(MoveResponse { dry_run, moves, rewrites }))
+        .content_type(mime::JSON)
+        .build())
+}
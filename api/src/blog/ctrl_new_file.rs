@@ -1,5 +1,5 @@
 use crate::blog::config::Config;
-use crate::blog::error::http_error;
+use crate::blog::error::{http_error, http_retry_error};
 use std::fs;
 use std::path::Path;
 use tide::prelude::*;
@@ -13,6 +13,13 @@ struct NewFile {
 pub async fn ctrl_new_file(mut req: Request<Config>) -> tide::Result {
     let NewFile { file } = req.body_json().await?;
 
+    let _lock = match req.state().repo_lock.try_write("new file") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
     let path_str = format!("{}/{}", req.state().get_input_path().to_string_lossy(), file);
     let path = Path::new(path_str.as_str());
     if path.exists() {
@@ -1,5 +1,5 @@
 use crate::blog::config::Config;
-use crate::blog::error::http_error;
+use crate::blog::error::{http_error, http_retry_error};
 use std::fs;
 use std::path::Path;
 use tide::prelude::*;
@@ -13,6 +13,13 @@ struct NewFolder {
 pub async fn ctrl_new_folder(mut req: Request<Config>) -> tide::Result {
     let NewFolder { folder } = req.body_json().await?;
 
+    let _lock = match req.state().repo_lock.try_write("new folder") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
     let path_str = format!("{}/{}", req.state().get_input_path().to_string_lossy(), folder);
     let path = Path::new(path_str.as_str());
     if path.exists() {
@@ -0,0 +1,33 @@
+use crate::blog::config::Config;
+use crate::blog::error::http_error;
+use serde::Serialize;
+use serde_json::json;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct NewJob {
+    kind: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NewJobResponse {
+    pub id: u64,
+}
+
+pub async fn ctrl_new_job(mut req: Request<Config>) -> tide::Result {
+    let NewJob { kind } = req.body_json().await?;
+
+    let id = match req.state().jobs.enqueue(req.state(), kind.as_str()) {
+        Ok(id) => id,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+
+    Ok(Response::builder(StatusCode::Accepted)
+        .body(json!(NewJobResponse { id }))
+        .content_type(mime::JSON)
+        .build())
+}
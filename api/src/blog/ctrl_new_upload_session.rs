@@ -0,0 +1,25 @@
+use crate::blog::config::Config;
+use crate::blog::error::http_error;
+use crate::blog::upload_sessions::create_session;
+use serde_json::json;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct NewUploadSession {
+    name: String,
+    size: u64,
+}
+
+pub async fn ctrl_new_upload_session(mut req: Request<Config>) -> tide::Result {
+    let NewUploadSession { name, size } = req.body_json().await?;
+
+    match create_session(req.state(), name.as_str(), size) {
+        Ok(session) => Ok(Response::builder(StatusCode::Created)
+            .body(json!(session))
+            .content_type(mime::JSON)
+            .build()),
+        Err(e) => Ok(http_error(e.status, e.message)),
+    }
+}
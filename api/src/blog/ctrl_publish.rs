@@ -0,0 +1,36 @@
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::publisher::publish;
+use serde_json::json;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct PublishData {
+    #[serde(default)]
+    push: bool,
+}
+
+pub async fn ctrl_publish(mut req: Request<Config>) -> tide::Result {
+    let PublishData { push } = req.body_json().await?;
+
+    let _lock = match req.state().repo_lock.try_write("publish") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    let deployment = match publish(req.state(), push) {
+        Ok(deployment) => deployment,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+
+    Ok(Response::builder(StatusCode::Created)
+        .body(json!(deployment))
+        .content_type(mime::JSON)
+        .build())
+}
@@ -0,0 +1,48 @@
+use crate::blog::config::Config;
+use crate::blog::error::http_error;
+use crate::blog::upload_sessions::{open_chunk, write_chunk};
+use async_std::io::ReadExt;
+use serde_json::json;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct ChunkQuery {
+    offset: u64,
+}
+
+// appends the raw body at offset, data received before a dropped connection is kept
+pub async fn ctrl_put_upload_chunk(mut req: Request<Config>) -> tide::Result {
+    let ChunkQuery { offset } = req.query()?;
+    let id = req.param("id")?.to_string();
+
+    let (mut session, mut file) = match open_chunk(req.state(), id.as_str(), offset) {
+        Ok(chunk) => chunk,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+
+    let mut body = req.take_body();
+    let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+    loop {
+        let n = match body.read(&mut buffer).await {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) => {
+                return Ok(http_error(StatusCode::BadRequest, format!("chunk interrupted at {}: {}", session.offset, e)));
+            }
+        };
+        if let Err(e) = write_chunk(&mut session, &mut file, &buffer[..n]) {
+            return Ok(http_error(e.status, e.message));
+        }
+    }
+
+    Ok(Response::builder(StatusCode::Ok)
+        .body(json!(session))
+        .content_type(mime::JSON)
+        .build())
+}
@@ -1,7 +1,11 @@
 use crate::blog::config::Config;
-use crate::blog::error::http_error;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::links::{apply_link_rewrites, plan_link_rewrites, LinkRewrite};
+use serde::Serialize;
+use serde_json::json;
 use std::fs;
 use std::path::Path;
+use tide::http::mime;
 use tide::prelude::*;
 use tide::{Request, Response, StatusCode};
 
@@ -9,10 +13,28 @@ use tide::{Request, Response, StatusCode};
 struct RenameFile {
     file: String,
     new_file: String,
+    // rewrites links and images of all posts pointing to the renamed path
+    #[serde(default)]
+    update_links: bool,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct RenameResponse {
+    dry_run: bool,
+    rewrites: Vec<LinkRewrite>,
 }
 
 pub async fn ctrl_rename(mut req: Request<Config>) -> tide::Result {
-    let RenameFile { file, new_file } = req.body_json().await?;
+    let RenameFile { file, new_file, update_links, dry_run } = req.body_json().await?;
+
+    let _lock = match req.state().repo_lock.try_write("rename") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
 
     let path_str = format!("{}/{}", req.state().get_input_path().to_string_lossy(), file);
     let path = Path::new(path_str.as_str());
@@ -26,9 +48,34 @@ pub async fn ctrl_rename(mut req: Request<Config>) -> tide::Result {
         return Ok(Response::builder(StatusCode::Conflict).build());
     }
 
-    if let Err(e) = fs::rename(path, new_path) {
-        return Ok(http_error(StatusCode::InternalServerError, format!("{}", e)));
+    let rewrites = if update_links {
+        match plan_link_rewrites(req.state(), &vec![(file.clone(), new_file.clone())]) {
+            Ok(rewrites) => rewrites,
+            Err(e) => {
+                return Ok(http_error(e.status, e.message));
+            }
+        }
+    } else {
+        vec![]
+    };
+
+    if !dry_run {
+        if let Err(e) = fs::rename(path, new_path) {
+            return Ok(http_error(StatusCode::InternalServerError, format!("{}", e)));
+        }
+        if let Err(e) = apply_link_rewrites(req.state(), &rewrites) {
+            // undo the rename, so paths and links stay consistent
+            let _ = fs::rename(new_path, path);
+            return Ok(http_error(e.status, e.message));
+        }
+    }
+
+    if !update_links && !dry_run {
+        return Ok(Response::builder(StatusCode::NoContent).build());
     }
 
-    Ok(Response::builder(StatusCode::NoContent).build())
+    Ok(Response::builder(StatusCode::Ok)
+        .body(json!(RenameResponse { dry_run, rewrites }))
+        .content_type(mime::JSON)
+        .build())
 }
\ No newline at end of file
@@ -0,0 +1,59 @@
+use crate::blog::auth_middleware::get_user;
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::tags::{apply_tag_change, plan_tag_change, TagChange};
+use crate::blog::trash::TrashItem;
+use serde::Serialize;
+use serde_json::json;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct RenameTag {
+    tag: String,
+    // merges into the tag if it is in use already
+    new_tag: String,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct RenameTagResponse {
+    dry_run: bool,
+    change: TagChange,
+    trash: Option<TrashItem>,
+}
+
+pub async fn ctrl_rename_tag(mut req: Request<Config>) -> tide::Result {
+    let RenameTag { tag, new_tag, dry_run } = req.body_json().await?;
+
+    let _lock = match req.state().repo_lock.try_write("rename tag") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    let change = match plan_tag_change(req.state(), tag.as_str(), Some(new_tag.as_str())) {
+        Ok(change) => change,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+
+    let mut trash = None;
+    if !dry_run {
+        trash = match apply_tag_change(req.state(), &change, get_user(&req).as_str()) {
+            Ok(trash) => trash,
+            Err(e) => {
+                return Ok(http_error(e.status, e.message));
+            }
+        };
+    }
+
+    Ok(Response::builder(StatusCode::Ok)
+        .body(json!(RenameTagResponse { dry_run, change, trash }))
+        .content_type(mime::JSON)
+        .build())
+}
@@ -0,0 +1,31 @@
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::trash::restore_from_trash;
+use serde_json::json;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct RestoreData {
+    id: String,
+}
+
+pub async fn ctrl_restore_trash(mut req: Request<Config>) -> tide::Result {
+    let RestoreData { id } = req.body_json().await?;
+
+    let _lock = match req.state().repo_lock.try_write("restore") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    match restore_from_trash(req.state(), id.as_str()) {
+        Ok(item) => Ok(Response::builder(StatusCode::Ok)
+            .body(json!(item))
+            .content_type(mime::JSON)
+            .build()),
+        Err(e) => Ok(http_error(e.status, e.message)),
+    }
+}
@@ -1,5 +1,5 @@
 use crate::blog::config::{Config, DEFAULT_BRANCH};
-use crate::blog::error::http_error;
+use crate::blog::error::{http_error, http_retry_error};
 use crate::blog::utils::{get_changes, Change};
 use git2::build::CheckoutBuilder;
 use git2::Repository;
@@ -16,6 +16,13 @@ struct RevertFile {
 pub async fn ctrl_revert(mut req: Request<Config>) -> tide::Result {
     let RevertFile { file } = req.body_json().await?;
 
+    let _lock = match req.state().repo_lock.try_write("revert") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
     let repo_path = req.state().get_input_path();
     let repo = match Repository::open(repo_path) {
         Ok(repo) => repo,
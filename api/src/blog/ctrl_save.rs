@@ -1,6 +1,13 @@
+use crate::blog::auth_middleware::get_user;
 use crate::blog::config::Config;
-use crate::blog::error::http_error;
+use crate::blog::drafts::discard_draft;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::utils::{content_hash, load_merge_base, merge_contents, store_merge_base};
+use git2::Repository;
+use serde::Serialize;
+use serde_json::json;
 use std::fs;
+use tide::http::mime;
 use tide::prelude::*;
 use tide::{Request, Response, StatusCode};
 
@@ -8,14 +15,82 @@ use tide::{Request, Response, StatusCode};
 struct SaveData {
     file: String,
     content: String,
+    base_hash: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SaveConflictResponse {
+    pub current_hash: String,
+    pub clean: bool,
+    pub merged: Option<String>,
 }
 
 pub async fn ctrl_save(mut req: Request<Config>) -> tide::Result {
-    let SaveData { file, content } = req.body_json().await?;
+    let if_match = req
+        .header("If-Match")
+        .map(|header| header.last().as_str().trim_start_matches("W/").trim_matches('"').to_string());
+    let SaveData { file, content, base_hash } = req.body_json().await?;
+
+    let _lock = match req.state().repo_lock.try_write("save") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    let base_hash = match if_match.or(base_hash) {
+        Some(base_hash) => base_hash,
+        None => {
+            return Ok(http_error(StatusCode::PreconditionRequired, "If-Match header or base_hash is required"));
+        }
+    };
+
+    // a missing file counts as empty, e.g. after it was removed in the meantime
+    let path_str = format!("{}/{}", req.state().get_input_path().to_string_lossy(), file);
+    let current = fs::read(path_str.as_str()).unwrap_or_default();
+    let current_hash = content_hash(&current);
+
+    if current_hash != base_hash {
+        let repo = match Repository::open(req.state().get_input_path()) {
+            Ok(repo) => repo,
+            Err(e) => {
+                return Ok(http_error(StatusCode::InternalServerError, format!("failed to open: {}", e.message())));
+            }
+        };
+
+        // the merge is only possible if the base is known
+        let merge_result = load_merge_base(req.state(), &repo, base_hash.as_str())
+            .map(|base| merge_contents(&repo, file.as_str(), &base, &current, content.as_bytes()));
+        let (clean, merged) = match merge_result {
+            Some(Ok((clean, merged))) => (clean, Some(merged)),
+            _ => (false, None),
+        };
 
-    if let Err(e) = fs::write(format!("{}/{}", req.state().get_input_path().to_string_lossy(), file), content) {
+        return Ok(Response::builder(StatusCode::Conflict)
+            .header("ETag", format!("\"{}\"", current_hash))
+            .body(json!(SaveConflictResponse { current_hash, clean, merged }))
+            .content_type(mime::JSON)
+            .build());
+    }
+
+    if let Err(e) = fs::write(path_str.as_str(), content.as_bytes()) {
         return Ok(http_error(StatusCode::InternalServerError, format!("unable to save: {}", e)));
     }
 
-    Ok(Response::builder(StatusCode::NoContent).build())
-}
\ No newline at end of file
+    // the saved content supersedes the autosave
+    if let Err(e) = discard_draft(req.state(), get_user(&req).as_str(), file.as_str()) {
+        return Ok(http_error(e.status, e.message));
+    }
+
+    // the saved content is the base of the next save
+    let hash = match store_merge_base(req.state(), content.as_bytes()) {
+        Ok(hash) => hash,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("unable to keep merge base: {}", e)));
+        }
+    };
+
+    Ok(Response::builder(StatusCode::NoContent)
+        .header("ETag", format!("\"{}\"", hash))
+        .build())
+}
@@ -0,0 +1,25 @@
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::search::{search_posts, SearchQuery};
+use serde_json::json;
+use tide::http::mime;
+use tide::{Request, Response, StatusCode};
+
+pub async fn ctrl_search(req: Request<Config>) -> tide::Result {
+    let query: SearchQuery = req.query()?;
+
+    let _lock = match req.state().repo_lock.try_read() {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    match search_posts(req.state(), &query) {
+        Ok(result) => Ok(Response::builder(StatusCode::Ok)
+            .body(json!(result))
+            .content_type(mime::JSON)
+            .build()),
+        Err(e) => Ok(http_error(e.status, e.message)),
+    }
+}
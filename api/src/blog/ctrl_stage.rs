@@ -1,5 +1,5 @@
 use crate::blog::config::{Config, DEFAULT_BRANCH};
-use crate::blog::error::http_error;
+use crate::blog::error::{http_error, http_retry_error};
 use git2::{IndexAddOption, Repository};
 use std::path::Path;
 use tide::prelude::*;
@@ -14,6 +14,13 @@ struct StageFile {
 pub async fn ctrl_stage(mut req: Request<Config>) -> tide::Result {
     let StageFile { file, stage } = req.body_json().await?;
 
+    let _lock = match req.state().repo_lock.try_write("stage") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
     let path_str = format!("{}/{}", req.state().get_input_path().to_string_lossy(), file);
     let path = Path::new(path_str.as_str());
     if !path.exists() && file != "*" {
@@ -46,7 +53,7 @@ pub async fn ctrl_stage(mut req: Request<Config>) -> tide::Result {
         let diff = repo
             .diff_tree_to_workdir_with_index(Some(&reference.peel_to_commit().unwrap().tree().unwrap()), None).unwrap();
 
-        for diff_delta in diff.deltas().into_iter() {
+        for diff_delta in diff.deltas() {
             let file_path = diff_delta.old_file().path().unwrap();
             if file != "*" && file != file_path.to_string_lossy() {
                 continue;
@@ -0,0 +1,49 @@
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use git2::{ErrorCode, Repository, StashApplyOptions};
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct StashApply {
+    index: usize,
+    #[serde(default)]
+    pop: bool,
+}
+
+pub async fn ctrl_stash_apply(mut req: Request<Config>) -> tide::Result {
+    let StashApply { index, pop } = req.body_json().await?;
+
+    let _lock = match req.state().repo_lock.try_write("stash") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    let repo_path = req.state().get_input_path();
+    let mut repo = match Repository::open(repo_path) {
+        Ok(repo) => repo,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("failed to open: {}", e.message())));
+        }
+    };
+
+    // restores what was staged, not only the working tree
+    let mut stash_options = StashApplyOptions::new();
+    stash_options.reinstantiate_index();
+    let result = if pop {
+        repo.stash_pop(index, Some(&mut stash_options))
+    } else {
+        repo.stash_apply(index, Some(&mut stash_options))
+    };
+    if let Err(e) = result {
+        return match e.code() {
+            ErrorCode::NotFound => Ok(Response::builder(StatusCode::NotFound).build()),
+            ErrorCode::Conflict => Ok(http_error(StatusCode::Conflict, format!("stash conflicts with local changes: {}", e.message()))),
+            _ => Ok(http_error(StatusCode::InternalServerError, format!("unable to apply stash: {}", e.message()))),
+        };
+    }
+
+    Ok(Response::builder(StatusCode::NoContent).build())
+}
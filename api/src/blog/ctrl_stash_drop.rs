@@ -0,0 +1,38 @@
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use git2::{ErrorCode, Repository};
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct StashDrop {
+    index: usize,
+}
+
+pub async fn ctrl_stash_drop(mut req: Request<Config>) -> tide::Result {
+    let StashDrop { index } = req.body_json().await?;
+
+    let _lock = match req.state().repo_lock.try_write("stash") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    let repo_path = req.state().get_input_path();
+    let mut repo = match Repository::open(repo_path) {
+        Ok(repo) => repo,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("failed to open: {}", e.message())));
+        }
+    };
+
+    if let Err(e) = repo.stash_drop(index) {
+        if e.code() == ErrorCode::NotFound {
+            return Ok(Response::builder(StatusCode::NotFound).build());
+        }
+        return Ok(http_error(StatusCode::InternalServerError, format!("unable to drop stash: {}", e.message())));
+    }
+
+    Ok(Response::builder(StatusCode::NoContent).build())
+}
@@ -0,0 +1,51 @@
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use git2::{ErrorCode, Repository, StashFlags};
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+#[derive(Debug, Deserialize)]
+struct StashPush {
+    message: Option<String>,
+    include_untracked: Option<bool>,
+}
+
+pub async fn ctrl_stash_push(mut req: Request<Config>) -> tide::Result {
+    let StashPush { message, include_untracked } = req.body_json().await?;
+
+    let _lock = match req.state().repo_lock.try_write("stash") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    let repo_path = req.state().get_input_path();
+    let mut repo = match Repository::open(repo_path) {
+        Ok(repo) => repo,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("failed to open: {}", e.message())));
+        }
+    };
+
+    let signature = match repo.signature() {
+        Ok(signature) => signature,
+        Err(e) => {
+            return Ok(http_error(StatusCode::InternalServerError, format!("missing signature: {}", e)));
+        }
+    };
+
+    let mut flags = StashFlags::DEFAULT;
+    if include_untracked.unwrap_or(true) {
+        flags |= StashFlags::INCLUDE_UNTRACKED;
+    }
+
+    if let Err(e) = repo.stash_save(&signature, message.as_deref().unwrap_or("stash"), Some(flags)) {
+        if e.code() == ErrorCode::NotFound {
+            return Ok(http_error(StatusCode::UnprocessableEntity, "no local changes to stash"));
+        }
+        return Ok(http_error(StatusCode::InternalServerError, format!("unable to stash: {}", e.message())));
+    }
+
+    Ok(Response::builder(StatusCode::NoContent).build())
+}
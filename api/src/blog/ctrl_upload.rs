@@ -1,11 +1,18 @@
 use crate::blog::config::Config;
-use crate::blog::error::http_error;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::upload::{UploadWriter, UploadedFile};
+use async_std::io::ReadExt;
 use base64::engine::general_purpose;
 use base64::Engine;
-use std::fs;
+use bytes::Bytes;
+use multer::Multipart;
+use serde_json::json;
 use std::path::Path;
+use tide::http::mime;
 use tide::prelude::*;
-use tide::{Request, Response, StatusCode};
+use tide::{Body, Request, Response, StatusCode};
+
+const READ_BUFFER_SIZE: usize = 64 * 1024;
 
 #[derive(Debug, Deserialize)]
 struct UploadData {
@@ -14,8 +21,16 @@ struct UploadData {
     content: String,
 }
 
+// multipart/form-data uploads are streamed to disk, json uploads with base64 content are still accepted
 pub async fn ctrl_upload(mut req: Request<Config>) -> tide::Result {
-    // body_form doesn't seem to work with file uploads...
+    let is_multipart = match req.content_type() {
+        Some(content_type) => content_type.essence() == "multipart/form-data",
+        None => false,
+    };
+    if is_multipart {
+        return upload_multipart(req).await;
+    }
+
     let UploadData {
         name,
         size,
@@ -23,37 +38,125 @@ pub async fn ctrl_upload(mut req: Request<Config>) -> tide::Result {
     } = req.body_json().await?;
 
     let decoded_content = general_purpose::STANDARD.decode(content)?;
+    if size != decoded_content.len() as i64 {
+        return Ok(Response::builder(StatusCode::UnprocessableEntity).build());
+    }
 
-    let path_str = format!(
-        "{}/{}",
-        req.state().get_input_path().to_string_lossy(),
-        name
-    );
-    let path = Path::new(path_str.as_str());
-    if path.exists() {
-        return Ok(Response::builder(StatusCode::Conflict).build());
+    let mut writer = match UploadWriter::create(req.state(), name.as_str()) {
+        Ok(writer) => writer,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+    if let Err(e) = writer.write(req.state(), &decoded_content) {
+        return Ok(http_error(e.status, e.message));
     }
 
-    match path.parent() {
-        Some(p) => {
-            if !p.exists() {
-                if let Err(e) = fs::create_dir(p) {
-                    return Ok(http_error(StatusCode::InternalServerError, format!("unable to create dir: {}", e)));
-                }
+    let _lock = match req.state().repo_lock.try_write("upload") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+    if let Err(e) = writer.finish(req.state()) {
+        return Ok(http_error(e.status, e.message));
+    }
+
+    Ok(Response::builder(StatusCode::Created).build())
+}
+
+// expects a "folder" field relative to the posts folder followed by one or more "file" fields
+async fn upload_multipart(mut req: Request<Config>) -> tide::Result {
+    let boundary = match req.header("Content-Type").map(|header| multer::parse_boundary(header.as_str())) {
+        Some(Ok(boundary)) => boundary,
+        _ => {
+            return Ok(http_error(StatusCode::BadRequest, "missing multipart boundary"));
+        }
+    };
+
+    let body = req.take_body();
+    let stream = futures_util::stream::unfold(body, |mut body: Body| async move {
+        let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+        match body.read(&mut buffer).await {
+            Ok(0) => None,
+            Ok(n) => {
+                buffer.truncate(n);
+                Some((Ok(Bytes::from(buffer)), body))
             }
+            Err(e) => Some((Err(e), body)),
         }
-        None => {
-            return Ok(http_error(StatusCode::InternalServerError, "Invalid directory"));
+    });
+    let mut multipart = Multipart::new(stream, boundary);
+
+    // the files are streamed into temporary files without holding the repo lock
+    let mut folder = String::new();
+    let mut writers: Vec<UploadWriter> = vec![];
+    let result: Result<(), (StatusCode, String)> = async {
+        loop {
+            let mut field = match multipart.next_field().await {
+                Ok(Some(field)) => field,
+                Ok(None) => break,
+                Err(e) => return Err((StatusCode::BadRequest, format!("invalid multipart body: {}", e))),
+            };
+
+            match field.name() {
+                Some("folder") => {
+                    folder = match field.text().await {
+                        Ok(text) => text,
+                        Err(e) => return Err((StatusCode::BadRequest, format!("invalid folder: {}", e))),
+                    };
+                }
+                Some("file") => {
+                    // only the base name of the client's file is used
+                    let file_name = match field.file_name().and_then(|name| Path::new(name).file_name()) {
+                        Some(file_name) => file_name.to_string_lossy().to_string(),
+                        None => return Err((StatusCode::BadRequest, String::from("file without name"))),
+                    };
+                    let name = if folder.is_empty() { file_name } else { format!("{}/{}", folder, file_name) };
+
+                    let mut writer = UploadWriter::create(req.state(), name.as_str()).map_err(|e| (e.status, e.message))?;
+                    loop {
+                        match field.chunk().await {
+                            Ok(Some(chunk)) => writer.write(req.state(), &chunk).map_err(|e| (e.status, e.message))?,
+                            Ok(None) => break,
+                            Err(e) => return Err((StatusCode::BadRequest, format!("upload of {} failed: {}", name, e))),
+                        }
+                    }
+                    writers.push(writer);
+                }
+                _ => {}
+            }
         }
+        Ok(())
     }
-
-    if size != decoded_content.len() as i64 {
-        return Ok(Response::builder(StatusCode::UnprocessableEntity).build());
+    .await;
+    if let Err((status, message)) = result {
+        return Ok(http_error(status, message));
     }
 
-    if let Err(e) = fs::write(path, decoded_content) {
-        return Ok(http_error(StatusCode::InternalServerError, format!("unable to write: {}", e)));
+    let _lock = match req.state().repo_lock.try_write("upload") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+
+    // a request either uploads all files or none
+    let mut uploaded_files: Vec<UploadedFile> = vec![];
+    for writer in writers {
+        match writer.finish(req.state()) {
+            Ok(uploaded_file) => uploaded_files.push(uploaded_file),
+            Err(e) => {
+                for uploaded_file in uploaded_files.iter() {
+                    let _ = std::fs::remove_file(req.state().get_input_path().join(uploaded_file.name.as_str()));
+                }
+                return Ok(http_error(e.status, e.message));
+            }
+        }
     }
 
-    Ok(Response::builder(StatusCode::Created).build())
-}
\ No newline at end of file
+    Ok(Response::builder(StatusCode::Created)
+        .body(json!(uploaded_files))
+        .content_type(mime::JSON)
+        .build())
+}
@@ -0,0 +1,105 @@
+use crate::blog::archive::{Archive, ArchivePlan};
+use crate::blog::config::Config;
+use crate::blog::error::{http_error, http_retry_error};
+use crate::blog::upload::UploadedFile;
+use async_std::io::ReadExt;
+use serde::Serialize;
+use serde_json::json;
+use std::io::Write;
+use tide::http::mime;
+use tide::prelude::*;
+use tide::{Request, Response, StatusCode};
+
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct ArchiveQuery {
+    // target folder relative to the posts folder, usually the asset folder of a post
+    folder: String,
+    #[serde(default)]
+    dry_run: bool,
+    // processes extracted images like uploaded ones, defaults to PROCESS_IMAGES
+    process_images: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+struct ArchiveResponse {
+    dry_run: bool,
+    plan: ArchivePlan,
+    files: Vec<UploadedFile>,
+}
+
+// extracts a zip, tar or tar.gz archive sent as raw body
+pub async fn ctrl_upload_archive(mut req: Request<Config>) -> tide::Result {
+    let ArchiveQuery { folder, dry_run, process_images } = req.query()?;
+
+    let (mut archive, mut file) = match Archive::create(req.state()) {
+        Ok(archive) => archive,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+
+    let mut body = req.take_body();
+    let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+    let mut size: u64 = 0;
+    loop {
+        let n = match body.read(&mut buffer).await {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) => {
+                return Ok(http_error(StatusCode::BadRequest, format!("upload interrupted: {}", e)));
+            }
+        };
+        size += n as u64;
+        if size > req.state().archive_max_size {
+            return Ok(http_error(
+                StatusCode::PayloadTooLarge,
+                format!("archive exceeds the limit of {} bytes", req.state().archive_max_size),
+            ));
+        }
+        if let Err(e) = file.write_all(&buffer[..n]) {
+            return Ok(http_error(StatusCode::InternalServerError, format!("unable to store archive: {}", e)));
+        }
+    }
+    drop(file);
+
+    if let Err(e) = archive.detect_format() {
+        return Ok(http_error(e.status, e.message));
+    }
+
+    // the archive is received without the repo lock, it is only needed to plan and extract
+    let _lock = match req.state().repo_lock.try_write("upload archive") {
+        Ok(lock) => lock,
+        Err(e) => {
+            return Ok(http_retry_error(e.status, e.message));
+        }
+    };
+    let plan = match archive.plan(req.state(), folder.as_str()) {
+        Ok(plan) => plan,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+
+    if dry_run || !plan.conflicts.is_empty() {
+        let status = if plan.conflicts.is_empty() { StatusCode::Ok } else { StatusCode::Conflict };
+        return Ok(Response::builder(status)
+            .body(json!(ArchiveResponse { dry_run, plan, files: vec![] }))
+            .content_type(mime::JSON)
+            .build());
+    }
+
+    let process_images = process_images.unwrap_or(req.state().process_images);
+    let files = match archive.extract(req.state(), folder.as_str(), process_images) {
+        Ok(files) => files,
+        Err(e) => {
+            return Ok(http_error(e.status, e.message));
+        }
+    };
+
+    Ok(Response::builder(StatusCode::Created)
+        .body(json!(ArchiveResponse { dry_run, plan, files }))
+        .content_type(mime::JSON)
+        .build())
+}
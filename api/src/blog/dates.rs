@@ -0,0 +1,153 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone};
+use serde::{Serialize, Serializer};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use tera::{Filter, Value};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const DATE_TIME_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"];
+const DATE_TIME_OFFSET_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M%:z", "%Y-%m-%d %H:%M:%S%:z", "%Y-%m-%d %H:%M%:z"];
+
+// the created attribute of a post, dates without a timezone are taken as utc
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostDate {
+    datetime: DateTime<FixedOffset>,
+    has_time: bool,
+}
+
+impl PostDate {
+    // accepts YYYY-MM-DD, optionally followed by a time (separated by "T" or a space) and an offset
+    pub fn parse(value: &str) -> Result<PostDate, String> {
+        let value = value.trim();
+        let utc = FixedOffset::east_opt(0).unwrap();
+
+        if let Ok(date) = NaiveDate::parse_from_str(value, DATE_FORMAT) {
+            return Ok(PostDate {
+                datetime: utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap()),
+                has_time: false,
+            });
+        }
+        if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+            return Ok(PostDate { datetime, has_time: true });
+        }
+        for format in DATE_TIME_OFFSET_FORMATS {
+            if let Ok(datetime) = DateTime::parse_from_str(value, format) {
+                return Ok(PostDate { datetime, has_time: true });
+            }
+        }
+        for format in DATE_TIME_FORMATS {
+            if let Ok(datetime) = NaiveDateTime::parse_from_str(value, format) {
+                return Ok(PostDate {
+                    datetime: utc.from_utc_datetime(&datetime),
+                    has_time: true,
+                });
+            }
+        }
+        Err(format!("invalid date '{}', expected YYYY-MM-DD with an optional time and timezone", value))
+    }
+
+    pub fn format(&self, format: &str) -> String {
+        self.datetime.format(format).to_string()
+    }
+}
+
+// dates compare by their point in time, which is the same for all timezones
+impl Ord for PostDate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.datetime.cmp(&other.datetime)
+    }
+}
+
+impl PartialOrd for PostDate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// serialized as it was written: a plain date or rfc3339
+impl Serialize for PostDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.has_time {
+            serializer.serialize_str(self.datetime.to_rfc3339().as_str())
+        } else {
+            serializer.serialize_str(self.format(DATE_FORMAT).as_str())
+        }
+    }
+}
+
+pub fn is_valid_format(format: &str) -> bool {
+    !StrftimeItems::new(format).any(|item| item == Item::Error)
+}
+
+// `{{ post.created | post_date }}` formats a created date with the configured format,
+// `post_date(format="%d.%m.%Y")` overrides it, a missing date is formatted as empty string
+pub struct PostDateFilter {
+    pub format: String,
+}
+
+impl Filter for PostDateFilter {
+    fn filter(&self, value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        let value = match value {
+            Value::String(value) => value.as_str(),
+            Value::Null => return Ok(Value::String(String::new())),
+            _ => return Err(tera::Error::msg("post_date filter expects a string")),
+        };
+        let date = PostDate::parse(value).map_err(tera::Error::msg)?;
+        let format = match args.get("format") {
+            Some(format) => match format.as_str() {
+                Some(format) if is_valid_format(format) => format.to_string(),
+                _ => return Err(tera::Error::msg(format!("post_date filter got an invalid format: {}", format))),
+            },
+            None => self.format.clone(),
+        };
+        Ok(Value::String(date.format(format.as_str())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dates_and_times() {
+        let date = PostDate::parse("2024-03-01").unwrap();
+        assert_eq!(date.format("%d.%m.%Y %H:%M"), "01.03.2024 00:00");
+        assert_eq!(serde_json::to_string(&date).unwrap(), "\"2024-03-01\"");
+
+        for value in ["2024-03-01T10:20", "2024-03-01 10:20", "2024-03-01T10:20:00", " 2024-03-01 10:20:00 "] {
+            let date = PostDate::parse(value).unwrap();
+            assert_eq!(serde_json::to_string(&date).unwrap(), "\"2024-03-01T10:20:00+00:00\"", "{}", value);
+        }
+    }
+
+    #[test]
+    fn keeps_the_offset() {
+        let date = PostDate::parse("2024-03-01T10:20:00+02:00").unwrap();
+        assert_eq!(date.format("%H:%M %:z"), "10:20 +02:00");
+        assert_eq!(PostDate::parse("2024-03-01 10:20+02:00").unwrap(), date);
+        assert_eq!(PostDate::parse("2024-03-01T08:20:00Z").unwrap().cmp(&date), Ordering::Equal);
+        assert!(PostDate::parse("2024-03-01T09:00:00Z").unwrap() > date);
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for value in ["", "yesterday", "01.03.2024", "2024-3-1x", "2024-13-01", "2023-02-29", "2024-03-01T25:00", "2024-03-01 10:20 CET"] {
+            assert!(PostDate::parse(value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn formats_missing_dates_as_empty() {
+        let filter = PostDateFilter { format: String::from("%Y") };
+        let args = HashMap::new();
+        assert_eq!(filter.filter(&Value::Null, &args).unwrap(), Value::String(String::new()));
+        assert_eq!(filter.filter(&Value::from("2024-03-01"), &args).unwrap(), Value::from("2024"));
+    }
+
+    #[test]
+    fn validates_formats() {
+        assert!(is_valid_format("%d.%m.%Y"));
+        assert!(!is_valid_format("%Q"));
+    }
+}
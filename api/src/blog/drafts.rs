@@ -0,0 +1,113 @@
+use crate::blog::config::Config;
+use crate::blog::error::ApiError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tide::StatusCode;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Draft {
+    pub file: String,
+    pub user: String,
+    pub time: u64,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DraftInfo {
+    pub file: String,
+    pub user: String,
+    pub time: u64,
+    pub size: usize,
+}
+
+// drafts are stored per user with the hex encoded file name, so nested paths stay flat
+fn get_draft_path(config: &Config, user: &str, file: &str) -> PathBuf {
+    config.get_drafts_path().join(user).join(format!("{}.json", hex::encode(file)))
+}
+
+pub fn save_draft(config: &Config, user: &str, file: &str, content: String) -> Result<Draft, ApiError> {
+    let draft = Draft {
+        file: file.to_string(),
+        user: user.to_string(),
+        time: match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs(),
+            Err(_) => 0,
+        },
+        content,
+    };
+
+    let content = match serde_json::to_string(&draft) {
+        Ok(content) => content,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to serialize draft: {}", e)));
+        }
+    };
+    if let Err(e) = fs::create_dir_all(config.get_drafts_path().join(user))
+        .and_then(|_| fs::write(get_draft_path(config, user, file), content))
+    {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to write draft: {}", e)));
+    }
+
+    Ok(draft)
+}
+
+pub fn get_draft(config: &Config, user: &str, file: &str) -> Result<Option<Draft>, ApiError> {
+    let path = get_draft_path(config, user, file);
+    if !path.exists() {
+        return Ok(None);
+    }
+    read_draft(&path).map(Some)
+}
+
+pub fn list_drafts(config: &Config, user: &str) -> Result<Vec<DraftInfo>, ApiError> {
+    let path = config.get_drafts_path().join(user);
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let entries = match fs::read_dir(&path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to list drafts: {}", e)));
+        }
+    };
+    let mut drafts = vec![];
+    for entry in entries.filter_map(|e| e.ok()) {
+        let draft = read_draft(&entry.path())?;
+        drafts.push(DraftInfo {
+            file: draft.file,
+            user: draft.user,
+            time: draft.time,
+            size: draft.content.len(),
+        });
+    }
+    drafts.sort_by_key(|draft| std::cmp::Reverse(draft.time));
+    Ok(drafts)
+}
+
+// returns whether a draft existed
+pub fn discard_draft(config: &Config, user: &str, file: &str) -> Result<bool, ApiError> {
+    let path = get_draft_path(config, user, file);
+    if !path.exists() {
+        return Ok(false);
+    }
+    match fs::remove_file(path) {
+        Ok(_) => Ok(true),
+        Err(e) => Err(ApiError::new(StatusCode::InternalServerError, format!("unable to discard draft: {}", e))),
+    }
+}
+
+fn read_draft(path: &PathBuf) -> Result<Draft, ApiError> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read draft: {}", e)));
+        }
+    };
+    match serde_json::from_str(content.as_str()) {
+        Ok(draft) => Ok(draft),
+        Err(e) => Err(ApiError::new(StatusCode::InternalServerError, format!("unable to parse draft: {}", e))),
+    }
+}
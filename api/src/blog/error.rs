@@ -3,6 +3,8 @@ use std::fmt::Display;
 use tide::Response;
 use tide::{Body, StatusCode};
 
+const RETRY_AFTER_SECONDS: &str = "1";
+
 #[derive(Debug, Clone)]
 pub struct GeneratorError {
     pub message: String,
@@ -16,15 +18,43 @@ impl Display for GeneratorError {
 
 impl GeneratorError {
     pub fn new(message: String) -> GeneratorError {
-        return GeneratorError {
+        GeneratorError {
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: String) -> ApiError {
+        ApiError {
+            status,
             message,
-        };
+        }
     }
 }
 
 pub fn http_error(status: StatusCode, body: impl Into<Body>) -> Response {
-    let response = Response::builder(status)
+    Response::builder(status)
         .body(body)
-        .build();
-    return response;
-}
\ No newline at end of file
+        .build()
+}
+
+// for temporary conflicts, e.g. a locked repository, tells the client when to retry
+pub fn http_retry_error(status: StatusCode, body: impl Into<Body>) -> Response {
+    Response::builder(status)
+        .header("Retry-After", RETRY_AFTER_SECONDS)
+        .body(body)
+        .build()
+}
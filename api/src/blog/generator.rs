@@ -1,6 +1,9 @@
-use crate::blog::config::HIGHLIGHT_THEME;
+use crate::blog::config::{PostStatus, HIGHLIGHT_THEME};
+use crate::blog::dates::{PostDate, PostDateFilter};
 use crate::blog::error::GeneratorError;
-use crate::blog::utils::find_files;
+use crate::blog::redirects::{get_redirects, write_redirects, REDIRECTS_FILE};
+use crate::blog::tags::get_cutout_path;
+use crate::blog::utils::{find_files, File};
 use crate::Config;
 use bytebuffer::ByteBuffer;
 use comrak::adapters::SyntaxHighlighterAdapter;
@@ -16,17 +19,17 @@ use std::fs;
 use std::fs::{create_dir, remove_dir_all};
 use std::io::{Read, Write};
 use std::iter::FromIterator;
-use std::ops::Index;
-use std::path::{Path, PathBuf};
+use std::ops::{Index, Range};
+use std::path::{Component, Path, PathBuf};
 use std::process::Command;
 use std::time::Instant;
 use tera::{Context, Tera};
 
-const DEFAULT_FILTER: &'static str = ".md";
-const KNOWN_ATTRIBUTES: &'static [&str] = &["created", "status", "tag"];
-const CUSTOM_POSTS: &'static [&str] = &["recent-posts.md", "overview.md"];
-const STATIC_PAGES: &'static [&str] = &["about.md", "contact.md"];
-const ESCAPABLE_CHARACTERS: &'static [char] = &[
+const DEFAULT_FILTER: &str = ".md";
+const KNOWN_ATTRIBUTES: &[&str] = &["created", "status", "tag"];
+const CUSTOM_POSTS: &[&str] = &["recent-posts.md", "overview.md"];
+const STATIC_PAGES: &[&str] = &["about.md", "contact.md"];
+const ESCAPABLE_CHARACTERS: &[char] = &[
     '\\', '`', '*', '_', '{', '}', '[', ']', '(', ')', '#', '+', '-', '.', '!',
 ];
 
@@ -34,14 +37,24 @@ const ESCAPABLE_CHARACTERS: &'static [char] = &[
 pub struct Post {
     filename: String,
     tags: Vec<String>,
-    created: String,
+    // none for the static pages and posts without created date, which are listed last
+    created: Option<PostDate>,
     status: Option<String>,
+    // label and colour of the status, if it is a known status
+    status_info: Option<PostStatus>,
     links: Vec<String>,
     images: Vec<String>,
     preview_images: Vec<(String, String)>,
     headline_ids: Vec<String>,
 }
 
+// counts of the rendered markdown of a post (after its attributes were removed)
+#[derive(Debug, Serialize)]
+pub struct PostStatistics {
+    pub headlines: usize,
+    pub words: usize,
+}
+
 #[derive(Serialize)]
 struct Headline {
     htype: String,
@@ -70,6 +83,9 @@ pub struct Generator<'a> {
     headline_regex: Option<Regex>,
     image_regex: Option<Regex>,
     log_buffer: Option<ByteBuffer>,
+    log_sink: Option<&'a dyn Fn(&str)>,
+    statuses: Option<&'a [PostStatus]>,
+    strict_statuses: bool,
 }
 
 impl<'a> Generator<'a> {
@@ -115,6 +131,9 @@ impl<'a> Generator<'a> {
             headline_regex: None,
             image_regex: None,
             log_buffer: None,
+            log_sink: None,
+            statuses: None,
+            strict_statuses: false,
         };
         generator
             .markdown_plugins
@@ -124,18 +143,38 @@ impl<'a> Generator<'a> {
     }
 
     fn clear_output_path(&self) {
-        remove_dir_all(&self.output_path.to_string_lossy().as_ref()).unwrap();
-        create_dir(&self.output_path.to_string_lossy().as_ref()).unwrap();
+        remove_dir_all(self.output_path.to_string_lossy().as_ref()).unwrap();
+        create_dir(self.output_path.to_string_lossy().as_ref()).unwrap();
     }
 
     fn set_filter(&mut self, filter: String) {
         self.filter = filter;
     }
 
+    // passes the status metadata to the templates, strict rejects posts with other statuses
+    pub fn set_statuses(&mut self, statuses: &'a [PostStatus], strict: bool) {
+        self.statuses = Some(statuses);
+        self.strict_statuses = strict;
+    }
+
     pub fn log_to_buffer(&mut self) {
         self.log_buffer = Some(ByteBuffer::new());
     }
 
+    // additionally passes every buffered log message to the sink, e.g. for streaming
+    pub fn log_to_sink(&mut self, sink: &'a dyn Fn(&str)) {
+        self.log_sink = Some(sink);
+    }
+
+    fn write_log(&mut self, message: String) {
+        if let Some(log_buffer) = self.log_buffer.as_mut() {
+            let _ = log_buffer.write_all(message.as_bytes());
+        }
+        if let Some(log_sink) = self.log_sink {
+            log_sink(message.as_str());
+        }
+    }
+
     pub fn get_log_result(&mut self) -> String {
         if self.log_buffer.is_some() {
             let mut output = String::new();
@@ -186,7 +225,7 @@ impl<'a> Generator<'a> {
                     return Err(GeneratorError::new(format!(
                         "unable to generate post {}: {}",
                         file.name,
-                        e.to_string()
+                        e
                     )));
                 }
             };
@@ -217,10 +256,11 @@ impl<'a> Generator<'a> {
             // create recent posts
             self.log_time(Some("Generating recent-posts.html"), true);
 
+            // posts without created date are listed last
             posts.sort_by(|a, b| b.created.cmp(&a.created));
             let filtered_posts: &mut Vec<Post> = &mut vec![];
             for post in posts.iter() {
-                if post.clone().created.len() > 0 {
+                if !STATIC_PAGES.contains(&post.filename.as_str()) {
                     filtered_posts.push(post.clone());
                 }
             }
@@ -298,6 +338,11 @@ impl<'a> Generator<'a> {
         self.log_time(None, false);
 
         if self.filter == DEFAULT_FILTER {
+            self.log_time(Some("Writing redirects"), false);
+            let redirects = get_redirects(&self.input_path)?;
+            write_redirects(&self.output_path, &redirects)?;
+            self.log_time(None, false);
+
             self.log_time(Some("Verifying links"), false);
             self.verify_links(posts)?;
             self.log_time(None, false);
@@ -318,7 +363,7 @@ impl<'a> Generator<'a> {
             Err(e) => {
                 return Err(GeneratorError::new(format!(
                     "unable to generate post preview: {}",
-                    e.to_string()
+                    e
                 )));
             }
         };
@@ -332,23 +377,19 @@ impl<'a> Generator<'a> {
     }
 
     fn log_time(&mut self, name: Option<&str>, flush: bool) {
-        if let Some(log_buffer) = self.log_buffer.as_mut() {
-            if name.is_some() {
-                let _ = log_buffer.write_all(
-                    format!("{:.2?} {}...", self.instant.elapsed(), name.unwrap()).as_bytes(),
-                );
+        if self.log_buffer.is_some() {
+            if let Some(name) = name {
+                self.write_log(format!("{:.2?} {}...", self.instant.elapsed(), name));
                 if flush {
-                    let _ = log_buffer.write_all("\n".as_bytes());
+                    self.write_log(String::from("\n"));
                 }
             } else {
-                let _ = log_buffer.write_all(
-                    format!(" Done (took {:.2?})\n", self.last_instant.elapsed()).as_bytes(),
-                );
+                self.write_log(format!(" Done (took {:.2?})\n", self.last_instant.elapsed()));
                 self.last_instant = Instant::now();
             }
         } else {
-            if name.is_some() {
-                print!("{:.2?} {}...", self.instant.elapsed(), name.unwrap());
+            if let Some(name) = name {
+                print!("{:.2?} {}...", self.instant.elapsed(), name);
                 if flush {
                     println!();
                 }
@@ -362,19 +403,20 @@ impl<'a> Generator<'a> {
     pub fn generate_preview_images(&self, posts: &Vec<Post>) -> Result<(), GeneratorError> {
         for post in posts {
             for preview_image in post.preview_images.iter() {
-                let mut output_path = PathBuf::from(self.output_path.clone());
+                let mut output_path = self.output_path.clone();
                 output_path.push(preview_image.1.clone());
                 if !output_path.exists() {
                     let output_base_path = output_path.parent().unwrap();
                     if !output_base_path.exists() {
                         if let Err(e) = create_dir(output_base_path) {
                             return Err(GeneratorError::new(format!(
-                                "Unable to create output directory: {}",
-                                output_base_path.to_string_lossy()
+                                "Unable to create output directory {}: {}",
+                                output_base_path.to_string_lossy(),
+                                e
                             )));
                         }
                     }
-                    let mut input_path = PathBuf::from(self.output_path.clone());
+                    let mut input_path = self.output_path.clone();
                     input_path.push(preview_image.0.clone());
 
                     Command::new("convert")
@@ -399,7 +441,7 @@ impl<'a> Generator<'a> {
     pub fn remove_exif_data(&self, posts: &Vec<Post>) -> Result<(), GeneratorError> {
         for post in posts {
             for image in post.images.iter() {
-                let mut image_path = PathBuf::from(self.output_path.clone());
+                let mut image_path = self.output_path.clone();
                 image_path.push(image.clone());
 
                 let meta: Metadata = match Metadata::new_from_path(&image_path) {
@@ -408,7 +450,7 @@ impl<'a> Generator<'a> {
                         return Err(GeneratorError::new(format!(
                             "Unable to get metadata for {}: {}",
                             image_path.to_string_lossy(),
-                            e.to_string()
+                            e
                         )));
                     }
                 };
@@ -419,7 +461,7 @@ impl<'a> Generator<'a> {
                         Err(e) => {
                             return Err(GeneratorError::new(format!(
                                 "Unable to clear Exif data: {}",
-                                e.to_string()
+                                e
                             )));
                         }
                     }
@@ -430,140 +472,130 @@ impl<'a> Generator<'a> {
     }
 
     fn verify_links(&self, posts: &Vec<Post>) -> Result<(), GeneratorError> {
+        let input_exists = |path: &Path| self.input_path.join(path).exists();
+        let output_exists = |path: &Path| self.output_path.join(path).exists();
         for post in posts {
-            'linkLoop: for link in post.links.iter() {
-                // skip external links
-                if link.starts_with("http") {
-                    continue;
-                }
-
-                let mut input_path = PathBuf::from(self.input_path.clone());
-                input_path.push(link);
-                if input_path.exists() {
-                    continue;
-                }
-
-                let mut url = link.clone();
-                let mut headline_id: Option<String> = None;
-                match link.split_once('#') {
-                    Some(elems) => {
-                        url = String::from(elems.0);
-                        headline_id = Some(String::from(elems.1));
-                    }
-                    None => {}
-                };
-
-                if url.ends_with(".html") {
-                    let mut output_path = PathBuf::from(self.output_path.clone());
-                    output_path.push(url.clone());
-                    if output_path.exists() {
-                        match headline_id {
-                            Some(headline_id) => {
-                                let md_file_name = url.replace(".html", ".md");
-                                for p in posts {
-                                    if p.filename != md_file_name {
-                                        continue;
-                                    }
-                                    if p.headline_ids.contains(&headline_id) {
-                                        continue 'linkLoop;
-                                    }
-                                }
-                                return Err(GeneratorError::new(format!(
-                                    "link not found: {} (unknown headline_id)",
-                                    link
-                                )));
-                            }
-                            None => continue,
-                        };
-                    }
-                }
-                return Err(GeneratorError::new(format!(
-                    "link not found: {} (unknown file)",
-                    link
-                )));
+            for link in post.links.iter() {
+                self.verify_link(link, posts, &input_exists, &output_exists)?;
             }
         }
         Ok(())
     }
 
-    fn check_unused_files(
-        &mut self,
+    fn verify_link(
+        &self,
+        link: &String,
         posts: &Vec<Post>,
-        tag_list: &Vec<String>,
+        input_exists: &dyn Fn(&Path) -> bool,
+        output_exists: &dyn Fn(&Path) -> bool,
     ) -> Result<(), GeneratorError> {
-        let files = find_files(&self.input_path, None);
+        // skip external links
+        if link.starts_with("http") {
+            return Ok(());
+        }
 
-        let filtered_files: &mut Vec<String> = &mut vec![];
-        for file in files.iter() {
-            if file.is_dir || file.name.ends_with(".md") || file.name.ends_with(".html") {
-                continue;
-            }
-            filtered_files.push(file.name.clone());
+        if input_exists(Path::new(link)) {
+            return Ok(());
         }
 
-        for post in posts {
-            for link in post.links.iter() {
-                // skip external links
-                if link.starts_with("http") {
-                    continue;
-                }
+        let mut url = link.clone();
+        let mut headline_id: Option<String> = None;
+        if let Some(elems) = link.split_once('#') {
+            url = String::from(elems.0);
+            headline_id = Some(String::from(elems.1));
+        }
 
-                let handle = &link.replace("../posts/", "");
-                // super weird: binary_search and such only operate 9 half-random values
-                match search(filtered_files, handle) {
-                    Some(i) => {
-                        filtered_files.swap_remove(i);
+        if url.ends_with(".html") && output_exists(Path::new(url.as_str())) {
+            match headline_id {
+                Some(headline_id) => {
+                    let md_file_name = url.replace(".html", ".md");
+                    for p in posts {
+                        if p.filename != md_file_name {
+                            continue;
+                        }
+                        if p.headline_ids.contains(&headline_id) {
+                            return Ok(());
+                        }
                     }
-                    None => {}
+                    return Err(GeneratorError::new(format!(
+                        "link not found: {} (unknown headline_id)",
+                        link
+                    )));
                 }
-            }
+                None => return Ok(()),
+            };
+        }
+        Err(GeneratorError::new(format!(
+            "link not found: {} (unknown file)",
+            link
+        )))
+    }
 
-            for image in post.images.iter() {
-                let handle = &image.replace("../posts/", "");
-                match search(filtered_files, handle) {
-                    Some(i) => {
-                        filtered_files.swap_remove(i);
-                    }
-                    None => {}
+    // runs scanner, rendering and link checks on the given (filename, content) sources
+    // without writing anything; `known_files` holds all file names relative to the input path
+    pub fn validate(
+        &mut self,
+        sources: &[(String, String)],
+        known_files: &[String],
+    ) -> Vec<String> {
+        let mut problems: Vec<String> = vec![];
+        let mut posts: Vec<Post> = vec![];
+
+        for (filename, content) in sources.iter() {
+            let mut file_content = content.clone();
+            let mut post = match self.new_post(filename.clone(), &mut file_content) {
+                Ok(post) => post,
+                Err(e) => {
+                    problems.push(format!("unable to parse post {}: {}", filename, e));
+                    continue;
                 }
+            };
+            let extra_context = self.generate_extra_context(&post);
+            if let Err(e) = self.render(file_content, None, extra_context, Some(&mut post)) {
+                problems.push(format!("unable to render post {}: {}", filename, e));
+                continue;
             }
 
-            for preview in post.preview_images.iter() {
-                let handle = &preview.1.replace("../posts/", "");
-                match search(filtered_files, handle) {
-                    Some(i) => {
-                        filtered_files.swap_remove(i);
-                    }
-                    None => {}
-                }
+            // custom posts are not link checked during generate either
+            if !CUSTOM_POSTS.contains(&filename.as_str()) {
+                posts.push(post);
             }
         }
 
-        let mut found_overview_files: Vec<String> = vec![];
-        for file in filtered_files.iter() {
-            if file.starts_with("overview/") {
-                for tag in tag_list {
-                    if *file == format!("overview/{}_cutout.jpg", tag) {
-                        found_overview_files.push(file.to_string());
-                    }
-                }
+        let input_exists = |path: &Path| match relative_to(&self.input_path, path) {
+            Some(name) => known_files.contains(&name),
+            None => false,
+        };
+        let output_exists = |path: &Path| match relative_to(&self.output_path, path) {
+            Some(name) => {
+                name.ends_with(".html") && known_files.contains(&name.replace(".html", ".md"))
             }
-        }
-        for file in found_overview_files {
-            match search(filtered_files, &file) {
-                Some(i) => {
-                    filtered_files.swap_remove(i);
+            None => false,
+        };
+        for post in posts.iter() {
+            for link in post.links.iter() {
+                if let Err(e) = self.verify_link(link, &posts, &input_exists, &output_exists) {
+                    problems.push(format!("{}: {}", post.filename, e));
                 }
-                None => {}
             }
         }
 
-        if filtered_files.len() > 0 {
-            if let Some(log_buffer) = self.log_buffer.as_mut() {
-                let _ = log_buffer
-                    .write_all(format!("\nfound {} entries:\n", filtered_files.len()).as_bytes());
+        problems
+    }
+
+    fn check_unused_files(
+        &mut self,
+        posts: &Vec<Post>,
+        tag_list: &Vec<String>,
+    ) -> Result<(), GeneratorError> {
+        let files = find_files(&self.input_path, None);
+        let filtered_files = &find_unused_files(&files, posts, tag_list);
+
+        if !filtered_files.is_empty() {
+            if self.log_buffer.is_some() {
+                self.write_log(format!("\nfound {} entries:\n", filtered_files.len()));
                 for file in filtered_files.iter() {
-                    let _ = log_buffer.write_all(format!("{}\n", file).as_bytes());
+                    self.write_log(format!("{}\n", file));
                 }
             } else {
                 println!("\nfound {} entries:", filtered_files.len());
@@ -589,21 +621,25 @@ impl<'a> Generator<'a> {
             }
         }
 
-        if features.len() > 0 || post.status.is_some() {
+        if !features.is_empty() || post.status.is_some() {
             let mut context = Context::new();
 
-            if features.len() > 0 {
+            if !features.is_empty() {
                 context.insert("features", &features);
             }
 
-            if post.status.is_some() {
-                context.insert("status", &post.status.as_ref().unwrap());
+            if let Some(status) = &post.status {
+                context.insert("status", status);
+            }
+
+            if let Some(status_info) = &post.status_info {
+                context.insert("status_info", status_info);
             }
 
             return Some(context);
         }
 
-        return None;
+        None
     }
 
     fn render(
@@ -623,8 +659,8 @@ impl<'a> Generator<'a> {
         self.log_time(None, false);
 
         // append manually rendered html
-        if html_append.is_some() {
-            md.push_str(html_append.unwrap().to_owned().as_str());
+        if let Some(html_append) = html_append {
+            md.push_str(html_append);
         }
 
         self.log_time(Some("Replacing preview images"), false);
@@ -670,7 +706,7 @@ impl<'a> Generator<'a> {
         let mut headlines: Vec<Headline> = vec![];
         let mut headline_ids: Vec<String> = vec![];
         for cap in re.captures_iter(md.as_str()) {
-            if &cap[1] != &cap[3] {
+            if cap[1] != cap[3] {
                 return Err(GeneratorError::new(String::from(
                     "Unmatching headline tags found",
                 )));
@@ -687,18 +723,15 @@ impl<'a> Generator<'a> {
         // write to original Post if available
         let mut title: Option<String> = None;
         let mut description: Option<String> = None;
-        match post {
-            Some(post) => {
-                post.headline_ids = headline_ids;
-                let title_str = post.filename.clone().replace(".md", "").replace("_", " ");
-                title = Some(title_str.clone());
-
-                if !STATIC_PAGES.contains(&post.filename.as_str()) {
-                    description =
-                        Some(self.get_description(file_content.as_str(), title_str.as_str()));
-                }
+        if let Some(post) = post {
+            post.headline_ids = headline_ids;
+            let title_str = post.filename.clone().replace(".md", "").replace("_", " ");
+            title = Some(title_str.clone());
+
+            if !STATIC_PAGES.contains(&post.filename.as_str()) {
+                description =
+                    Some(self.get_description(file_content.as_str(), title_str.as_str()));
             }
-            None => {}
         }
 
         // add id to each headline
@@ -732,17 +765,17 @@ impl<'a> Generator<'a> {
         // render html
         let mut context = Context::new();
         context.insert("content", &md);
-        if filtered_headlines.len() > 0 {
+        if !filtered_headlines.is_empty() {
             context.insert("headlines", &filtered_headlines);
         }
-        if title.is_some() {
-            context.insert("title", &title.unwrap());
+        if let Some(title) = title {
+            context.insert("title", &title);
         }
-        if description.is_some() {
-            context.insert("description", &description.unwrap());
+        if let Some(description) = description {
+            context.insert("description", &description);
         }
-        if extra_context.is_some() {
-            context.extend(extra_context.unwrap());
+        if let Some(extra_context) = extra_context {
+            context.extend(extra_context);
         }
         let html = match self.tera.render("post.html", &context) {
             Ok(html) => html,
@@ -771,7 +804,7 @@ impl<'a> Generator<'a> {
                 return Err(GeneratorError::new(format!(
                     "unable to write file {}: {}",
                     target_filename.to_string_lossy(),
-                    e.to_string()
+                    e
                 )));
             }
         }
@@ -802,25 +835,24 @@ impl<'a> Generator<'a> {
 
         iter_nodes(
             root,
-            &|node, description, is_paragraph| match &mut node.data.borrow_mut().value {
-                &mut NodeValue::Paragraph => {
+            &|node, description, is_paragraph| match node.data.borrow_mut().value {
+                NodeValue::Paragraph => {
                     *is_paragraph = true;
                 }
-                &mut NodeValue::Link(_) => {
+                NodeValue::Link(_) => {
                     *is_paragraph = true;
                 }
-                &mut NodeValue::Text(ref text) => {
+                NodeValue::Text(ref text) => {
                     if description.matches('.').count() >= 3 {
                         return;
                     }
 
                     if *is_paragraph && !text.starts_with(title) {
                         if description.ends_with('.') {
-                            description.push_str(" ");
+                            description.push(' ');
                         }
                         description.push_str(text);
                     }
-                    return;
                 }
                 _ => {
                     *is_paragraph = false;
@@ -833,11 +865,159 @@ impl<'a> Generator<'a> {
         description
     }
 
+    // code blocks and html don't count as words, inline code is part of the text and does
+    pub fn get_statistics(&self, content: &str) -> PostStatistics {
+        let arena = Arena::new();
+        let root = parse_document(&arena, content, &self.markdown_options);
+        let mut statistics = PostStatistics {
+            headlines: 0,
+            words: 0,
+        };
+        for node in root.descendants() {
+            match &node.data.borrow().value {
+                NodeValue::Heading(_) => statistics.headlines += 1,
+                NodeValue::Text(text) => statistics.words += text.split_whitespace().count(),
+                NodeValue::Code(code) => statistics.words += code.literal.split_whitespace().count(),
+                _ => {}
+            }
+        }
+        statistics
+    }
+
     pub fn new_post(
         &self,
         filename: String,
         file_content: &mut String,
     ) -> Result<Post, GeneratorError> {
+        let tags = self.scan_tags(&filename, file_content)?;
+
+        let mut post = Post {
+            filename: filename.clone(),
+            tags: vec![],
+            created: None,
+            status: None,
+            status_info: None,
+            links: vec![],
+            images: vec![],
+            preview_images: vec![],
+            headline_ids: vec![],
+        };
+
+        // values are validated first, positions are only known before the content is modified
+        for tag in tags.iter() {
+            let value = tag.value.clone().unwrap_or_default();
+            match tag.name.as_str() {
+                "created" => match PostDate::parse(value.as_str()) {
+                    Ok(created) => post.created = Some(created),
+                    Err(e) => {
+                        let (line, column) = get_line_column(file_content, tag.pos.0);
+                        return Err(GeneratorError::new(format!(
+                            "{} in {}:{}:{}",
+                            e, filename, line, column
+                        )));
+                    }
+                },
+                "status" => {
+                    if let Some(statuses) = self.statuses {
+                        match statuses.iter().find(|status| status.name == value) {
+                            Some(status) => post.status_info = Some(status.clone()),
+                            None if !self.strict_statuses => {}
+                            None => {
+                                let (line, column) = get_line_column(file_content, tag.pos.0);
+                                return Err(GeneratorError::new(format!(
+                                    "unknown status '{}' in {}:{}:{}",
+                                    value, filename, line, column
+                                )));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut char_shift_pos: usize = 0;
+        for tag in tags.iter() {
+            // only react to specific tags
+            if KNOWN_ATTRIBUTES.contains(&tag.name.as_str()) {
+                match tag.name.as_str() {
+                    // parsed above
+                    "created" => {}
+                    "tag" => post.tags.push(tag.value.clone().unwrap()),
+                    "status" => post.status = Some(tag.value.clone().unwrap()),
+                    _ => {
+                        return Err(GeneratorError::new(format!(
+                            "found known attribute without handler '{}' in {}",
+                            tag.name, filename
+                        )));
+                    }
+                }
+
+                // if tag ends with newline, remove newline as well
+                let range_expand = {
+                    if file_content
+                        .index(tag.pos.1 - char_shift_pos..tag.pos.1 + 1 - char_shift_pos)
+                        == "\n"
+                    {
+                        1
+                    } else {
+                        0
+                    }
+                };
+
+                // remove tag from markdown
+                file_content.replace_range(
+                    tag.pos.0 - char_shift_pos..tag.pos.1 + range_expand - char_shift_pos,
+                    "",
+                );
+
+                // calculate position correction
+                char_shift_pos += (tag.pos.1 + range_expand) - tag.pos.0;
+            } else if tag.is_image {
+                post.images.push(tag.link.clone().unwrap());
+
+                if tag.class.is_some() && tag.class.as_ref().unwrap() == "preview" {
+                    let old_section = file_content
+                        .get(tag.pos.0 - char_shift_pos..tag.pos.1 - char_shift_pos)
+                        .unwrap();
+                    let mut without_tag = old_section.replace("{preview}", "");
+                    let last_slash_pos = without_tag.rfind('/').unwrap();
+                    without_tag.insert_str(last_slash_pos, "/preview");
+                    let length_diff = old_section.len() - without_tag.len();
+
+                    post.preview_images.push((
+                        tag.link.clone().unwrap(),
+                        self.to_preview_image_url(tag.link.as_ref().unwrap().to_string()),
+                    ));
+
+                    // replace image with preview image
+                    file_content.replace_range(
+                        tag.pos.0 - char_shift_pos..tag.pos.1 - char_shift_pos,
+                        without_tag.as_str(),
+                    );
+
+                    // calculate position correction
+                    char_shift_pos += length_diff;
+                }
+            } else if tag.link.is_some() {
+                post.links.push(tag.link.clone().unwrap());
+            } else if tag.link.is_none() && tag.value.is_some() {
+                return Err(GeneratorError::new(format!(
+                    "unknown attribute '{}' in {}",
+                    tag.name, filename
+                )));
+            }
+        }
+
+        Ok(post)
+    }
+
+    // finds tags, links and images with their byte positions in the content
+    fn scan_tags(
+        &self,
+        filename: &String,
+        file_content: &str,
+    ) -> Result<Vec<ScannerTag>, GeneratorError> {
         let mut prev_char = '\0';
         let mut prev_pos: usize = 0;
 
@@ -1072,89 +1252,55 @@ impl<'a> Generator<'a> {
             )));
         }
 
-        let mut post = Post {
-            filename: filename.clone(),
-            tags: vec![],
-            created: "".to_string(),
-            status: None,
-            links: vec![],
-            images: vec![],
-            preview_images: vec![],
-            headline_ids: vec![],
-        };
-        let mut char_shift_pos: usize = 0;
-        for tag in tags.iter() {
-            // only react to specific tags
-            if KNOWN_ATTRIBUTES.contains(&tag.name.as_str()) {
-                match tag.name.as_str() {
-                    "created" => post.created = tag.value.clone().unwrap(),
-                    "tag" => post.tags.push(tag.value.clone().unwrap()),
-                    "status" => post.status = Some(tag.value.clone().unwrap()),
-                    _ => {
-                        return Err(GeneratorError::new(format!(
-                            "found known attribute without handler '{}' in {}",
-                            tag.name, filename
-                        )));
-                    }
-                }
-
-                // if tag ends with newline, remove newline as well
-                let range_expand = {
-                    if file_content
-                        .index(tag.pos.1 - char_shift_pos..tag.pos.1 + 1 - char_shift_pos)
-                        == "\n"
-                    {
-                        1
-                    } else {
-                        0
-                    }
-                };
-
-                // remove tag from markdown
-                file_content.replace_range(
-                    tag.pos.0 - char_shift_pos..tag.pos.1 + range_expand - char_shift_pos,
-                    "",
-                );
-
-                // calculate position correction
-                char_shift_pos += (tag.pos.1 + range_expand) - tag.pos.0;
-            } else if tag.is_image {
-                post.images.push(tag.link.clone().unwrap());
-
-                if tag.class.is_some() && tag.class.as_ref().unwrap() == "preview" {
-                    let old_section = file_content
-                        .get(tag.pos.0 - char_shift_pos..tag.pos.1 - char_shift_pos)
-                        .unwrap();
-                    let mut without_tag = old_section.replace("{preview}", "");
-                    let last_slash_pos = without_tag.rfind('/').unwrap();
-                    without_tag.insert_str(last_slash_pos, "/preview");
-                    let length_diff = old_section.len() - without_tag.len();
-
-                    post.preview_images.push((
-                        tag.link.clone().unwrap(),
-                        self.to_preview_image_url(tag.link.as_ref().unwrap().to_string()),
-                    ));
-
-                    // replace image with preview image
-                    file_content.replace_range(
-                        tag.pos.0 - char_shift_pos..tag.pos.1 - char_shift_pos,
-                        without_tag.as_str(),
-                    );
+        Ok(tags)
+    }
 
-                    // calculate position correction
-                    char_shift_pos += length_diff;
+    // returns the targets of all links and images with their byte range in the content
+    pub fn scan_link_targets(
+        &self,
+        filename: &String,
+        file_content: &str,
+    ) -> Result<Vec<(Range<usize>, String)>, GeneratorError> {
+        let mut targets = vec![];
+        for tag in self.scan_tags(filename, file_content)? {
+            let link = match tag.link {
+                Some(link) => link,
+                None => continue,
+            };
+            let needle = format!("]({})", link);
+            match file_content[tag.pos.0..].find(needle.as_str()) {
+                Some(offset) => {
+                    let start = tag.pos.0 + offset + 2;
+                    targets.push((start..start + link.len(), link));
+                }
+                // escaped characters are not part of the scanned link
+                None => {
+                    return Err(GeneratorError::new(format!(
+                        "unable to locate link {} in {}",
+                        link, filename
+                    )));
                 }
-            } else if tag.link.is_some() {
-                post.links.push(tag.link.clone().unwrap());
-            } else if tag.link.is_none() && tag.value.is_some() {
-                return Err(GeneratorError::new(format!(
-                    "unknown attribute '{}' in {}",
-                    tag.name, filename
-                )));
             }
         }
+        Ok(targets)
+    }
 
-        Ok(post)
+    // returns the known attributes as (byte range, name, value), the range covers the brackets
+    pub fn scan_attributes(
+        &self,
+        filename: &String,
+        file_content: &str,
+    ) -> Result<Vec<(Range<usize>, String, String)>, GeneratorError> {
+        let mut attributes = vec![];
+        for tag in self.scan_tags(filename, file_content)? {
+            if !KNOWN_ATTRIBUTES.contains(&tag.name.as_str()) {
+                continue;
+            }
+            if let Some(value) = tag.value {
+                attributes.push((tag.pos.0..tag.pos.1, tag.name, value));
+            }
+        }
+        Ok(attributes)
     }
 
     fn get_tag_name_value(&self, input: String) -> (String, Option<String>) {
@@ -1166,7 +1312,7 @@ impl<'a> Generator<'a> {
 
     fn to_preview_image_url(&self, url: String) -> String {
         let parts = url.rsplit_once('/').unwrap();
-        return format!("{}/preview/{}", parts.0, parts.1);
+        format!("{}/preview/{}", parts.0, parts.1)
     }
 
     fn title2id(&self, title: String) -> String {
@@ -1174,7 +1320,120 @@ impl<'a> Generator<'a> {
     }
 }
 
-fn search(haystack: &Vec<String>, needle: &String) -> Option<usize> {
+// 1-based line and column (in characters) of a byte position
+fn get_line_column(content: &str, pos: usize) -> (usize, usize) {
+    let before = &content[..pos];
+    let line = before.matches('\n').count() + 1;
+    let column = match before.rfind('\n') {
+        Some(line_start) => before[line_start + 1..].chars().count() + 1,
+        None => before.chars().count() + 1,
+    };
+    (line, column)
+}
+
+// lexically resolves `path` against `base` and returns it relative to `base`
+fn relative_to(base: &PathBuf, path: &Path) -> Option<String> {
+    let mut resolved = PathBuf::new();
+    for component in base.join(path).components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            _ => resolved.push(component),
+        }
+    }
+    match resolved.strip_prefix(base) {
+        Ok(relative) => Some(relative.to_string_lossy().to_string()),
+        Err(_) => None,
+    }
+}
+
+impl Post {
+    pub fn get_filename(&self) -> &String {
+        &self.filename
+    }
+
+    pub fn get_tags(&self) -> &Vec<String> {
+        &self.tags
+    }
+
+    pub fn get_status(&self) -> Option<&String> {
+        self.status.as_ref()
+    }
+
+    pub fn get_created(&self) -> Option<&PostDate> {
+        self.created.as_ref()
+    }
+
+    pub fn get_links(&self) -> &Vec<String> {
+        &self.links
+    }
+
+    pub fn get_images(&self) -> &Vec<String> {
+        &self.images
+    }
+
+    // files below the input path referenced by links, images and preview images
+    pub fn get_referenced_files(&self) -> Vec<String> {
+        let links = self.links.iter().filter(|link| !link.starts_with("http"));
+        let previews = self.preview_images.iter().map(|preview| &preview.1);
+        links
+            .chain(self.images.iter())
+            .chain(previews)
+            .map(|file| file.replace("../posts/", ""))
+            .collect()
+    }
+}
+
+// returns the files (except markdown and html) no post references,
+// overview cutouts of known tags are used by the overview
+pub fn find_unused_files(
+    files: &[File],
+    posts: &Vec<Post>,
+    tag_list: &Vec<String>,
+) -> Vec<String> {
+    let filtered_files: &mut Vec<String> = &mut vec![];
+    for file in files.iter() {
+        if file.is_dir
+            || file.name.ends_with(".md")
+            || file.name.ends_with(".html")
+            || file.name == REDIRECTS_FILE
+        {
+            continue;
+        }
+        filtered_files.push(file.name.clone());
+    }
+
+    for post in posts {
+        for handle in post.get_referenced_files().iter() {
+            // super weird: binary_search and such only operate 9 half-random values
+            if let Some(i) = search(filtered_files, handle) {
+                filtered_files.swap_remove(i);
+            }
+        }
+    }
+
+    let mut found_overview_files: Vec<String> = vec![];
+    for file in filtered_files.iter() {
+        if file.starts_with("overview/") {
+            for tag in tag_list {
+                if *file == get_cutout_path(tag) {
+                    found_overview_files.push(file.to_string());
+                }
+            }
+        }
+    }
+    for file in found_overview_files {
+        if let Some(i) = search(filtered_files, &file) {
+            filtered_files.swap_remove(i);
+        }
+    }
+
+    filtered_files.to_vec()
+}
+
+fn search(haystack: &[String], needle: &String) -> Option<usize> {
     for (pos, elem) in haystack.iter().enumerate() {
         if elem == needle {
             return Some(pos);
@@ -1199,11 +1458,53 @@ pub fn generate_files(
         config.get_output_path(),
         Some(&adapter),
     );
+    generator.set_statuses(&config.statuses, config.strict_statuses);
 
     // set default for name filter
-    if name_arg.is_some() {
-        generator.set_filter(name_arg.unwrap().to_string());
+    if let Some(name_arg) = name_arg {
+        generator.set_filter(name_arg.to_string());
     }
 
     generator.generate()
 }
+
+// loads the templates of the working path with the custom filters registered
+pub fn new_tera(config: &Config) -> Result<Tera, tera::Error> {
+    let mut tera = Tera::new(format!("{}/templates/*.html", config.working_path).as_str())?;
+    tera.register_filter(
+        "post_date",
+        PostDateFilter {
+            format: config.date_format.clone(),
+        },
+    );
+    Ok(tera)
+}
+
+// generates all files while logging to a buffer, the log is returned regardless of the result
+pub fn generate_logged(config: &Config, log_sink: Option<&dyn Fn(&str)>) -> (Result<(), GeneratorError>, String) {
+    let tera = match new_tera(config) {
+        Ok(t) => t,
+        Err(e) => {
+            return (Err(GeneratorError::new(format!("unable to generate config: {}", e))), String::new());
+        }
+    };
+
+    let adapter = SyntectAdapter::new(Some(HIGHLIGHT_THEME));
+    let mut generator = Generator::new(
+        &tera,
+        config.get_input_path(),
+        config.get_output_path(),
+        Some(&adapter),
+    );
+    generator.set_statuses(&config.statuses, config.strict_statuses);
+    generator.log_to_buffer();
+    if let Some(log_sink) = log_sink {
+        generator.log_to_sink(log_sink);
+    }
+
+    let result = match generator.generate() {
+        Ok(()) => Ok(()),
+        Err(e) => Err(GeneratorError::new(format!("unable to generate file: {}", e.message))),
+    };
+    (result, generator.get_log_result())
+}
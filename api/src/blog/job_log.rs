@@ -0,0 +1,42 @@
+use crate::blog::config::Config;
+use serde::Serialize;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Serialize)]
+pub struct JobLogEntry {
+    pub time: u64,
+    pub job: String,
+    pub success: bool,
+    pub message: String,
+    pub log: String,
+}
+
+impl JobLogEntry {
+    pub fn new(job: &str, success: bool, message: String, log: String) -> JobLogEntry {
+        let time = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs(),
+            Err(_) => 0,
+        };
+        JobLogEntry {
+            time,
+            job: job.to_string(),
+            success,
+            message,
+            log,
+        }
+    }
+}
+
+// appends the entry as a json line to the job log
+pub fn append_job_log(config: &Config, entry: &JobLogEntry) -> Result<(), std::io::Error> {
+    let path = config.get_job_log_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let line = serde_json::to_string(entry)?;
+    file.write_all(format!("{}\n", line).as_bytes())
+}
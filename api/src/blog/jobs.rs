@@ -0,0 +1,232 @@
+use crate::blog::config::Config;
+use crate::blog::error::ApiError;
+use crate::blog::generator::generate_logged;
+use crate::blog::job_log::{append_job_log, JobLogEntry};
+use crate::blog::remote::{pull_remote, push_remote};
+use crate::blog::trash::purge_trash;
+use serde::Serialize;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tide::StatusCode;
+
+const MAX_FINISHED_JOBS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: u64,
+    pub kind: String,
+    pub state: JobState,
+    pub created: u64,
+    pub finished: Option<u64>,
+    pub status: Option<StatusCode>,
+    pub message: String,
+    pub log: String,
+}
+
+impl Job {
+    pub fn is_finished(&self) -> bool {
+        self.state == JobState::Succeeded || self.state == JobState::Failed
+    }
+}
+
+type JobTask = Box<dyn FnOnce(&dyn Fn(&str)) -> Result<String, ApiError> + Send>;
+
+struct JobsInner {
+    next_id: u64,
+    jobs: VecDeque<Job>,
+    sender: Option<Sender<(u64, JobTask)>>,
+}
+
+// runs generate and git operations one at a time on a worker thread
+#[derive(Clone)]
+pub struct Jobs {
+    inner: Arc<Mutex<JobsInner>>,
+}
+
+impl Jobs {
+    pub fn new() -> Self {
+        Jobs {
+            inner: Arc::new(Mutex::new(JobsInner {
+                next_id: 1,
+                jobs: VecDeque::new(),
+                sender: None,
+            })),
+        }
+    }
+
+    // queues a job of a known kind and returns its id
+    pub fn enqueue(&self, config: &Config, kind: &str) -> Result<u64, ApiError> {
+        let task = match create_task(config, kind) {
+            Some(task) => task,
+            None => {
+                return Err(ApiError::new(StatusCode::BadRequest, format!("unknown job kind: {}", kind)));
+            }
+        };
+
+        let mut inner = self.inner.lock().unwrap();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        inner.jobs.push_back(Job {
+            id,
+            kind: kind.to_string(),
+            state: JobState::Queued,
+            created: now(),
+            finished: None,
+            status: None,
+            message: String::new(),
+            log: String::new(),
+        });
+
+        // forget the oldest finished jobs
+        while inner.jobs.len() > MAX_FINISHED_JOBS {
+            match inner.jobs.iter().position(|job| job.is_finished()) {
+                Some(pos) => {
+                    inner.jobs.remove(pos);
+                }
+                None => break,
+            }
+        }
+
+        if inner.sender.is_none() {
+            inner.sender = Some(self.start_worker(config.clone()));
+        }
+        // the worker is gone, the job is handed to a new one
+        let unsent = match inner.sender.as_ref().unwrap().send((id, task)) {
+            Ok(()) => None,
+            Err(e) => Some(e.0),
+        };
+        if let Some(unsent) = unsent {
+            let sender = self.start_worker(config.clone());
+            if let Err(e) = sender.send(unsent) {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to queue job: {}", e)));
+            }
+            inner.sender = Some(sender);
+        }
+
+        Ok(id)
+    }
+
+    pub fn get(&self, id: u64) -> Option<Job> {
+        let inner = self.inner.lock().unwrap();
+        inner.jobs.iter().find(|job| job.id == id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<Job> {
+        let inner = self.inner.lock().unwrap();
+        inner.jobs.iter().cloned().collect()
+    }
+
+    fn update<F: FnOnce(&mut Job)>(&self, id: u64, f: F) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(job) = inner.jobs.iter_mut().find(|job| job.id == id) {
+            f(job);
+        }
+    }
+
+    fn start_worker(&self, config: Config) -> Sender<(u64, JobTask)> {
+        let (sender, receiver) = channel::<(u64, JobTask)>();
+        let jobs = self.clone();
+        thread::spawn(move || {
+            for (id, task) in receiver {
+                let kind = jobs.get(id).map(|job| job.kind).unwrap_or_default();
+                let lock = config.repo_lock.write(kind.as_str());
+                jobs.update(id, |job| job.state = JobState::Running);
+
+                let log_sink = |message: &str| jobs.update(id, |job| job.log.push_str(message));
+                // a panicking task fails its job instead of the worker
+                let result = match catch_unwind(AssertUnwindSafe(|| task(&log_sink))) {
+                    Ok(result) => result,
+                    Err(panic) => Err(ApiError::new(
+                        StatusCode::InternalServerError,
+                        format!("job panicked: {}", get_panic_message(panic.as_ref())),
+                    )),
+                };
+
+                jobs.update(id, |job| {
+                    job.finished = Some(now());
+                    match result {
+                        Ok(message) => {
+                            job.state = JobState::Succeeded;
+                            job.status = Some(StatusCode::Ok);
+                            job.message = message;
+                        }
+                        Err(e) => {
+                            job.state = JobState::Failed;
+                            job.status = Some(e.status);
+                            job.message = e.message;
+                        }
+                    }
+                });
+
+                // expired trash items are also removed while nobody looks at the trash
+                if let Err(e) = purge_trash(&config) {
+                    eprintln!("unable to purge trash: {}", e.message);
+                }
+                drop(lock);
+
+                if let Some(job) = jobs.get(id) {
+                    let entry = JobLogEntry::new(job.kind.as_str(), job.state == JobState::Succeeded, job.message, job.log);
+                    if let Err(e) = append_job_log(&config, &entry) {
+                        eprintln!("unable to write job log: {}", e);
+                    }
+                }
+            }
+        });
+        sender
+    }
+}
+
+fn create_task(config: &Config, kind: &str) -> Option<JobTask> {
+    let config = config.clone();
+    let task: JobTask = match kind {
+        "generate" => Box::new(move |log_sink| generate(&config, log_sink)),
+        "pull_remote" => Box::new(move |_| pull_remote(&config)),
+        "push_remote" => Box::new(move |_| push_remote(&config).map(|_| String::from("Pushed"))),
+        "hook_push" => Box::new(move |log_sink| {
+            let message = pull_remote(&config)?;
+            log_sink(format!("{}\n", message).as_str());
+            generate(&config, log_sink)
+        }),
+        _ => return None,
+    };
+    Some(task)
+}
+
+fn generate(config: &Config, log_sink: &dyn Fn(&str)) -> Result<String, ApiError> {
+    let (result, _) = generate_logged(config, Some(log_sink));
+    match result {
+        Ok(()) => Ok(String::from("Generated")),
+        Err(e) => Err(ApiError::new(StatusCode::InternalServerError, e.message)),
+    }
+}
+
+fn get_panic_message(panic: &(dyn Any + Send)) -> &str {
+    match panic.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => match panic.downcast_ref::<String>() {
+            Some(message) => message.as_str(),
+            None => "unknown error",
+        },
+    }
+}
+
+fn now() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs(),
+        Err(_) => 0,
+    }
+}
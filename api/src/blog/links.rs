@@ -0,0 +1,186 @@
+use crate::blog::config::Config;
+use crate::blog::error::ApiError;
+use crate::blog::generator::Generator;
+use crate::blog::utils::find_files;
+use serde::Serialize;
+use std::fs;
+use tera::Tera;
+use tide::StatusCode;
+
+// forms of relative links to files below the posts folder, images are written relative to the generated pages
+const RELATIVE_PREFIXES: &[&str] = &["../posts/", "./"];
+
+#[derive(Debug, Serialize)]
+pub struct LinkChange {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LinkRewrite {
+    // the post as named after the renames
+    pub file: String,
+    pub links: Vec<LinkChange>,
+    #[serde(skip)]
+    content: String,
+    #[serde(skip)]
+    original: String,
+}
+
+// scans all posts for links and images pointing to renamed paths, `renames` holds
+// (from, to) paths relative to the posts folder, folders are matched with everything below them
+pub fn plan_link_rewrites(config: &Config, renames: &Vec<(String, String)>) -> Result<Vec<LinkRewrite>, ApiError> {
+    let tera = Tera::default();
+    let generator = Generator::new(&tera, config.get_input_path(), config.get_output_path(), None);
+
+    let mut rewrites = vec![];
+    for file in find_files(&config.get_input_path(), Some(".md")) {
+        if file.is_dir {
+            continue;
+        }
+        let content = match fs::read_to_string(config.get_input_path().join(file.name.as_str())) {
+            Ok(content) => content,
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read {}: {}", file.name, e)));
+            }
+        };
+        let (new_content, links) = rewrite_content(&generator, &file.name, content.as_str(), renames)?;
+        if links.is_empty() {
+            continue;
+        }
+
+        rewrites.push(LinkRewrite {
+            file: renamed_path(file.name.as_str(), renames).unwrap_or(file.name),
+            links,
+            content: new_content,
+            original: content,
+        });
+    }
+    Ok(rewrites)
+}
+
+// writes the rewritten posts, the renames have to be applied before,
+// on failure the posts written so far are restored so the renames can be undone
+pub fn apply_link_rewrites(config: &Config, rewrites: &[LinkRewrite]) -> Result<(), ApiError> {
+    for (idx, rewrite) in rewrites.iter().enumerate() {
+        if let Err(e) = fs::write(config.get_input_path().join(rewrite.file.as_str()), rewrite.content.as_bytes()) {
+            for written in rewrites[..idx].iter() {
+                let _ = fs::write(config.get_input_path().join(written.file.as_str()), written.original.as_bytes());
+            }
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to write {}: {}", rewrite.file, e)));
+        }
+    }
+    Ok(())
+}
+
+fn rewrite_content(
+    generator: &Generator,
+    file: &String,
+    content: &str,
+    renames: &Vec<(String, String)>,
+) -> Result<(String, Vec<LinkChange>), ApiError> {
+    let targets = match generator.scan_link_targets(file, content) {
+        Ok(targets) => targets,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::UnprocessableEntity, e.message));
+        }
+    };
+
+    let mut new_content = content.to_string();
+    let mut links = vec![];
+    // replace from the back, so the ranges in front stay valid
+    for (range, link) in targets.into_iter().rev() {
+        if let Some(new_link) = rewrite_link(link.as_str(), renames) {
+            new_content.replace_range(range, new_link.as_str());
+            links.insert(0, LinkChange { from: link, to: new_link });
+        }
+    }
+    Ok((new_content, links))
+}
+
+fn renamed_path(path: &str, renames: &Vec<(String, String)>) -> Option<String> {
+    for (from, to) in renames {
+        if path == from {
+            return Some(to.clone());
+        }
+        if let Some(rest) = path.strip_prefix(format!("{}/", from).as_str()) {
+            return Some(format!("{}/{}", to, rest));
+        }
+    }
+    None
+}
+
+// links either point to files below the posts folder or to generated pages, both keep their anchor
+fn rewrite_link(link: &str, renames: &Vec<(String, String)>) -> Option<String> {
+    if link.starts_with("http") {
+        return None;
+    }
+
+    let (url, anchor) = match link.split_once('#') {
+        Some((url, anchor)) => (url, format!("#{}", anchor)),
+        None => (link, String::new()),
+    };
+    // renames are relative to the posts folder, the prefix is kept
+    let (prefix, url) = match RELATIVE_PREFIXES.iter().find(|prefix| url.starts_with(*prefix)) {
+        Some(prefix) => (*prefix, &url[prefix.len()..]),
+        None => ("", url),
+    };
+
+    if let Some(new_url) = renamed_path(url, renames) {
+        return Some(format!("{}{}{}", prefix, new_url, anchor));
+    }
+
+    let base_name = url.strip_suffix(".html")?;
+    let new_file = renamed_path(format!("{}.md", base_name).as_str(), renames)?;
+    let new_base_name = new_file.strip_suffix(".md")?;
+    Some(format!("{}{}.html{}", prefix, new_base_name, anchor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn rewrite(content: &str, renames: &[(&str, &str)]) -> String {
+        let tera = Tera::default();
+        let generator = Generator::new(&tera, PathBuf::from("posts"), PathBuf::from("p"), None);
+        let renames = renames.iter().map(|(from, to)| (from.to_string(), to.to_string())).collect();
+        rewrite_content(&generator, &String::from("Other.md"), content, &renames).unwrap().0
+    }
+
+    #[test]
+    fn rewrites_renamed_images() {
+        let content = "# Other\n\n![](../posts/Post/img.jpg)\n![](../posts/Post/other.jpg)\n";
+        assert_eq!(
+            rewrite(content, &[("Post/img.jpg", "Post/photo.jpg")]),
+            "# Other\n\n![](../posts/Post/photo.jpg)\n![](../posts/Post/other.jpg)\n"
+        );
+    }
+
+    #[test]
+    fn rewrites_moved_posts_with_their_assets() {
+        let content = "# Other\n\n[post](My_Post.html#intro) ![](../posts/My_Post/img.jpg) [pdf](./My_Post/paper.pdf)\n";
+        assert_eq!(
+            rewrite(content, &[("My_Post.md", "Moved.md"), ("My_Post", "Moved")]),
+            "# Other\n\n[post](Moved.html#intro) ![](../posts/Moved/img.jpg) [pdf](./Moved/paper.pdf)\n"
+        );
+    }
+
+    #[test]
+    fn rewrites_the_moved_post_itself() {
+        let tera = Tera::default();
+        let generator = Generator::new(&tera, PathBuf::from("posts"), PathBuf::from("p"), None);
+        let renames = vec![(String::from("My_Post.md"), String::from("Moved.md")), (String::from("My_Post"), String::from("Moved"))];
+        let content = "# My Post\n\n![](../posts/My_Post/img.jpg)\n";
+        let (new_content, links) = rewrite_content(&generator, &String::from("My_Post.md"), content, &renames).unwrap();
+        assert_eq!(new_content, "# My Post\n\n![](../posts/Moved/img.jpg)\n");
+        assert_eq!(links.len(), 1);
+        assert_eq!(renamed_path("My_Post.md", &renames), Some(String::from("Moved.md")));
+    }
+
+    #[test]
+    fn keeps_other_links() {
+        let content = "# Other\n\n[web](https://example.com/Post/img.jpg) ![](../posts/Posts/img.jpg)\n";
+        assert_eq!(rewrite(content, &[("Post", "Moved")]), content);
+    }
+}
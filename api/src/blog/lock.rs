@@ -0,0 +1,99 @@
+use crate::blog::error::ApiError;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use tide::StatusCode;
+
+static REPO_LOCKS: OnceLock<Mutex<HashMap<PathBuf, RepoLock>>> = OnceLock::new();
+
+struct LockState {
+    readers: usize,
+    writer: Option<String>,
+}
+
+// read/write lock around index and working tree mutations of a working path,
+// requests fail fast while the background jobs wait for their turn
+#[derive(Clone)]
+pub struct RepoLock {
+    state: Arc<(Mutex<LockState>, Condvar)>,
+}
+
+pub struct RepoReadGuard {
+    lock: RepoLock,
+}
+
+pub struct RepoWriteGuard {
+    lock: RepoLock,
+}
+
+impl RepoLock {
+    // returns the shared lock of the working path
+    pub fn for_path(working_path: &str) -> RepoLock {
+        let locks = REPO_LOCKS.get_or_init(|| Mutex::new(HashMap::new()));
+        let mut locks = locks.lock().unwrap();
+        locks
+            .entry(PathBuf::from(working_path))
+            .or_insert_with(|| RepoLock {
+                state: Arc::new((
+                    Mutex::new(LockState {
+                        readers: 0,
+                        writer: None,
+                    }),
+                    Condvar::new(),
+                )),
+            })
+            .clone()
+    }
+
+    pub fn try_read(&self) -> Result<RepoReadGuard, ApiError> {
+        let mut state = self.state.0.lock().unwrap();
+        if let Some(writer) = &state.writer {
+            return Err(busy(writer));
+        }
+        state.readers += 1;
+        Ok(RepoReadGuard { lock: self.clone() })
+    }
+
+    pub fn try_write(&self, operation: &str) -> Result<RepoWriteGuard, ApiError> {
+        let mut state = self.state.0.lock().unwrap();
+        if let Some(writer) = &state.writer {
+            return Err(busy(writer));
+        }
+        if state.readers > 0 {
+            return Err(busy("reading"));
+        }
+        state.writer = Some(operation.to_string());
+        Ok(RepoWriteGuard { lock: self.clone() })
+    }
+
+    // blocks until the lock is available
+    pub fn write(&self, operation: &str) -> RepoWriteGuard {
+        let (mutex, condvar) = &*self.state;
+        let mut state = mutex.lock().unwrap();
+        while state.writer.is_some() || state.readers > 0 {
+            state = condvar.wait(state).unwrap();
+        }
+        state.writer = Some(operation.to_string());
+        RepoWriteGuard { lock: self.clone() }
+    }
+}
+
+impl Drop for RepoReadGuard {
+    fn drop(&mut self) {
+        let (mutex, condvar) = &*self.lock.state;
+        mutex.lock().unwrap().readers -= 1;
+        condvar.notify_all();
+    }
+}
+
+impl Drop for RepoWriteGuard {
+    fn drop(&mut self) {
+        let (mutex, condvar) = &*self.lock.state;
+        mutex.lock().unwrap().writer = None;
+        condvar.notify_all();
+    }
+}
+
+fn busy(operation: &str) -> ApiError {
+    ApiError::new(StatusCode::Locked, format!("repository is busy ({}), retry later", operation))
+}
@@ -1,20 +1,79 @@
 pub mod auth_middleware;
+pub mod archive;
+pub mod assets;
 pub mod config;
+pub mod ctrl_autosave;
+pub mod ctrl_change_post_status;
 pub mod ctrl_commit;
 pub mod ctrl_generate;
 pub mod ctrl_push_remote;
 pub mod ctrl_pull_remote;
 pub mod ctrl_delete;
+pub mod ctrl_delete_assets;
+pub mod ctrl_delete_post;
+pub mod ctrl_delete_tag;
+pub mod ctrl_delete_upload_session;
+pub mod ctrl_empty_trash;
+pub mod ctrl_finalize_upload_session;
+pub mod ctrl_discard_autosave;
+pub mod ctrl_get_assets;
+pub mod ctrl_get_autosave;
+pub mod ctrl_get_autosaves;
 pub mod ctrl_get_changes;
+pub mod ctrl_get_deployments;
+pub mod ctrl_get_file;
 pub mod ctrl_get_files;
+pub mod ctrl_get_job;
+pub mod ctrl_get_job_events;
+pub mod ctrl_get_jobs;
+pub mod ctrl_get_posts;
 pub mod ctrl_get_preview;
+pub mod ctrl_get_redirects;
+pub mod ctrl_get_stashes;
+pub mod ctrl_get_status_history;
+pub mod ctrl_get_statuses;
+pub mod ctrl_get_sync_status;
+pub mod ctrl_get_tags;
+pub mod ctrl_get_trash;
+pub mod ctrl_get_upload_session;
+pub mod ctrl_move_post;
+pub mod ctrl_hook_push;
 pub mod ctrl_new_file;
 pub mod ctrl_new_folder;
+pub mod ctrl_new_job;
+pub mod ctrl_new_upload_session;
+pub mod ctrl_publish;
+pub mod ctrl_put_upload_chunk;
 pub mod ctrl_stage;
 pub mod ctrl_rename;
+pub mod ctrl_rename_tag;
+pub mod ctrl_restore_trash;
 pub mod ctrl_revert;
+pub mod ctrl_rollback_deployment;
 pub mod ctrl_save;
+pub mod ctrl_search;
+pub mod ctrl_stash_apply;
+pub mod ctrl_stash_drop;
+pub mod ctrl_stash_push;
 pub mod ctrl_upload;
+pub mod ctrl_upload_archive;
+pub mod dates;
+pub mod drafts;
 pub mod error;
 pub mod generator;
+pub mod job_log;
+pub mod jobs;
+pub mod lock;
+pub mod links;
+pub mod posts;
+pub mod publisher;
+pub mod redirects;
+pub mod remote;
+pub mod search;
+pub mod signing;
+pub mod status;
+pub mod tags;
+pub mod trash;
+pub mod upload;
+pub mod upload_sessions;
 pub mod utils;
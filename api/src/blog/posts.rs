@@ -0,0 +1,241 @@
+use crate::blog::config::Config;
+use crate::blog::dates::PostDate;
+use crate::blog::error::ApiError;
+use crate::blog::generator::Generator;
+use crate::blog::utils::{find_files, get_last_commits, join_relative, CommitSummary};
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tera::Tera;
+use tide::StatusCode;
+use walkdir::WalkDir;
+
+#[derive(Debug, Serialize)]
+pub struct PathMove {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostSort {
+    Created,
+    File,
+    Words,
+    Modified,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostsQuery {
+    pub tag: Option<String>,
+    pub status: Option<String>,
+    #[serde(default = "default_sort")]
+    pub sort: PostSort,
+    #[serde(default = "default_order")]
+    pub order: SortOrder,
+}
+
+fn default_sort() -> PostSort {
+    PostSort::Created
+}
+
+fn default_order() -> SortOrder {
+    SortOrder::Desc
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostSummary {
+    pub file: String,
+    pub created: Option<PostDate>,
+    pub tags: Vec<String>,
+    pub status: Option<String>,
+    pub headlines: usize,
+    pub words: usize,
+    pub links: usize,
+    pub images: usize,
+    // none for posts that were never committed
+    pub last_commit: Option<CommitSummary>,
+}
+
+// a post is its markdown file and the folder next to it with the same name holding its assets
+pub struct PostPaths {
+    pub file: String,
+    pub asset_dir: Option<String>,
+}
+
+impl PostPaths {
+    pub fn find(config: &Config, file: &str) -> Result<PostPaths, ApiError> {
+        let (path, asset_dir) = resolve_paths(config, file)?;
+        if !path.is_file() {
+            return Err(ApiError::new(StatusCode::NotFound, format!("post not found: {}", file)));
+        }
+        let has_assets = config.get_input_path().join(asset_dir.as_str()).is_dir();
+        Ok(PostPaths {
+            file: file.to_string(),
+            asset_dir: if has_assets { Some(asset_dir) } else { None },
+        })
+    }
+
+    // all files of the post relative to the posts folder, the markdown file first
+    pub fn list_files(&self, config: &Config) -> Vec<String> {
+        let mut files = vec![self.file.clone()];
+        if let Some(asset_dir) = &self.asset_dir {
+            let input_path = config.get_input_path();
+            let mut assets: Vec<String> = WalkDir::new(input_path.join(asset_dir))
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| !e.file_type().is_dir())
+                .map(|e| relative_path(&input_path, e.path()))
+                .collect();
+            assets.sort();
+            files.append(&mut assets);
+        }
+        files
+    }
+
+    // the moves of every file when renaming the post to new_file
+    pub fn plan_move(&self, config: &Config, new_file: &str) -> Result<Vec<PathMove>, ApiError> {
+        let (new_path, new_asset_dir) = resolve_paths(config, new_file)?;
+        let input_path = config.get_input_path();
+        if new_path.exists() {
+            return Err(ApiError::new(StatusCode::Conflict, format!("path already exists: {}", new_file)));
+        }
+        if self.asset_dir.is_some() && input_path.join(new_asset_dir.as_str()).exists() {
+            return Err(ApiError::new(StatusCode::Conflict, format!("path already exists: {}", new_asset_dir)));
+        }
+
+        Ok(self
+            .list_files(config)
+            .into_iter()
+            .map(|from| {
+                let to = match &self.asset_dir {
+                    Some(asset_dir) if from != self.file => {
+                        format!("{}{}", new_asset_dir, &from[asset_dir.len()..])
+                    }
+                    _ => new_file.to_string(),
+                };
+                PathMove { from, to }
+            })
+            .collect())
+    }
+}
+
+// parses every markdown file below the posts folder and returns the matching ones sorted
+pub fn list_posts(config: &Config, query: &PostsQuery) -> Result<Vec<PostSummary>, ApiError> {
+    let input_path = config.get_input_path();
+    let tera = Tera::default();
+    let generator = Generator::new(&tera, input_path.clone(), config.get_output_path(), None);
+
+    let mut posts = vec![];
+    for file in find_files(&input_path, Some(".md")) {
+        if file.is_dir {
+            continue;
+        }
+        let mut content = match fs::read_to_string(input_path.join(file.name.as_str())) {
+            Ok(content) => content,
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read {}: {}", file.name, e)));
+            }
+        };
+        let post = match generator.new_post(file.name.clone(), &mut content) {
+            Ok(post) => post,
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::UnprocessableEntity, format!("unable to parse {}: {}", file.name, e.message)));
+            }
+        };
+        if let Some(tag) = &query.tag {
+            if !post.get_tags().contains(tag) {
+                continue;
+            }
+        }
+        if let Some(status) = &query.status {
+            if post.get_status() != Some(status) {
+                continue;
+            }
+        }
+
+        let statistics = generator.get_statistics(content.as_str());
+        posts.push(PostSummary {
+            file: file.name,
+            created: post.get_created().cloned(),
+            tags: post.get_tags().clone(),
+            status: post.get_status().cloned(),
+            headlines: statistics.headlines,
+            words: statistics.words,
+            links: post.get_links().len(),
+            images: post.get_images().len(),
+            last_commit: None,
+        });
+    }
+
+    let names: HashSet<String> = posts.iter().map(|post| post.file.clone()).collect();
+    let mut last_commits = match Repository::open(&input_path) {
+        Ok(repo) => match get_last_commits(&repo, &names) {
+            Ok(last_commits) => last_commits,
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read history: {}", e.message())));
+            }
+        },
+        Err(_) => HashMap::new(),
+    };
+    for post in posts.iter_mut() {
+        post.last_commit = last_commits.remove(&post.file);
+    }
+
+    posts.sort_by(|a, b| {
+        let ordering = match query.sort {
+            PostSort::Created => a.created.cmp(&b.created),
+            PostSort::File => a.file.cmp(&b.file),
+            PostSort::Words => a.words.cmp(&b.words),
+            PostSort::Modified => {
+                let a_time = a.last_commit.as_ref().map(|commit| commit.time);
+                let b_time = b.last_commit.as_ref().map(|commit| commit.time);
+                a_time.cmp(&b_time)
+            }
+        };
+        // ties are always listed by file name
+        let ordering = match query.order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        };
+        match ordering {
+            Ordering::Equal => a.file.cmp(&b.file),
+            ordering => ordering,
+        }
+    });
+    Ok(posts)
+}
+
+// the markdown file and the asset folder of a post, both have to be below the posts folder
+fn resolve_paths(config: &Config, file: &str) -> Result<(PathBuf, String), ApiError> {
+    let asset_dir = get_asset_dir(file)?;
+    let invalid = || ApiError::new(StatusCode::BadRequest, format!("invalid post path: {}", file));
+    let path = join_relative(&config.get_input_path(), file).ok_or_else(invalid)?;
+    // e.g. "..md" would name the folder containing the post
+    let is_named = matches!(Path::new(asset_dir.as_str()).components().next_back(), Some(Component::Normal(_)));
+    if !is_named || asset_dir.ends_with('/') {
+        return Err(invalid());
+    }
+    Ok((path, asset_dir))
+}
+
+fn get_asset_dir(file: &str) -> Result<String, ApiError> {
+    match file.strip_suffix(".md") {
+        Some(base_name) if !base_name.is_empty() => Ok(base_name.to_string()),
+        _ => Err(ApiError::new(StatusCode::BadRequest, format!("not a markdown file: {}", file))),
+    }
+}
+
+fn relative_path(base: &PathBuf, path: &Path) -> String {
+    path.strip_prefix(base).unwrap_or(path).to_string_lossy().to_string()
+}
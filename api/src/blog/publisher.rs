@@ -0,0 +1,426 @@
+use crate::blog::config::{Config, REMOTE_NAME};
+use crate::blog::error::ApiError;
+use crate::blog::utils::{get_remote_callbacks, new_index_entry};
+use git2::{Index, PushOptions, Repository};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tide::StatusCode;
+use walkdir::WalkDir;
+
+// paths below the working path that make up the public site
+const PUBLISH_PATHS: &[&str] = &["index.html", "assets", "p"];
+// older deployments can no longer be rolled back to
+const MAX_DEPLOYMENTS: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Deployment {
+    pub id: u64,
+    pub time: u64,
+    pub source_commit: Option<String>,
+    pub branch_commit: Option<String>,
+    // relative path => sha256 of all files synced to the deploy directory
+    pub files: Option<BTreeMap<String, String>>,
+    pub rollback_of: Option<u64>,
+}
+
+pub fn get_deployments(config: &Config) -> Result<Vec<Deployment>, ApiError> {
+    let path = config.get_deployments_path();
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read deployments: {}", e)));
+        }
+    };
+    match serde_json::from_str(content.as_str()) {
+        Ok(deployments) => Ok(deployments),
+        Err(e) => Err(ApiError::new(StatusCode::InternalServerError, format!("unable to parse deployments: {}", e))),
+    }
+}
+
+// publishes the generated site to the configured branch and/or deploy directory
+pub fn publish(config: &Config, push: bool) -> Result<Deployment, ApiError> {
+    if config.publish_branch.is_none() && config.publish_dir.is_none() {
+        return Err(ApiError::new(StatusCode::PreconditionFailed, String::from("no PUBLISH_BRANCH or PUBLISH_DIR configured")));
+    }
+
+    if let Some(publish_dir) = &config.publish_dir {
+        check_publish_dir(config, Path::new(publish_dir))?;
+    }
+
+    // the contents are only stored for directory deployments, branches keep their own history
+    let files = find_site_files(config);
+    let mut checksums: BTreeMap<String, String> = BTreeMap::new();
+    for (name, path) in files.iter() {
+        let content = read_file(path)?;
+        let file_checksum = match config.publish_dir {
+            Some(_) => store_object(config, &content)?,
+            None => checksum(&content),
+        };
+        checksums.insert(name.clone(), file_checksum);
+    }
+
+    let source_commit = match Repository::open(config.get_input_path()) {
+        Ok(repo) => match repo.head().and_then(|head| head.peel_to_commit()) {
+            Ok(commit) => Some(commit.id().to_string()),
+            Err(_) => None,
+        },
+        Err(_) => None,
+    };
+
+    let mut branch_commit = None;
+    if let Some(branch) = &config.publish_branch {
+        let message = match &source_commit {
+            Some(source_commit) => format!("Publish {}", source_commit),
+            None => String::from("Publish"),
+        };
+        branch_commit = Some(commit_to_branch(config, branch, &files, message.as_str(), push)?);
+    }
+
+    let mut deployed_files = None;
+    if let Some(publish_dir) = &config.publish_dir {
+        sync_directory(config, Path::new(publish_dir), &checksums)?;
+        deployed_files = Some(checksums);
+    }
+
+    add_deployment(config, source_commit, branch_commit, deployed_files, None)
+}
+
+// restores the state of a previous deployment as a new deployment
+pub fn rollback(config: &Config, id: u64, push: bool) -> Result<Deployment, ApiError> {
+    let deployments = get_deployments(config)?;
+    let deployment = match deployments.iter().find(|deployment| deployment.id == id) {
+        Some(deployment) => deployment.clone(),
+        None => {
+            return Err(ApiError::new(StatusCode::NotFound, format!("deployment {} not found", id)));
+        }
+    };
+
+    let mut branch_commit = None;
+    if let (Some(branch), Some(old_commit)) = (&config.publish_branch, &deployment.branch_commit) {
+        let repo = open_publish_repo(config)?;
+        let tree = match git2::Oid::from_str(old_commit)
+            .and_then(|oid| repo.find_commit(oid))
+            .and_then(|commit| commit.tree())
+        {
+            Ok(tree) => tree,
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to find deployed tree: {}", e.message())));
+            }
+        };
+        let message = format!("Rollback to deployment {}", id);
+        branch_commit = Some(commit_tree(&repo, branch, &tree, message.as_str())?);
+        if push {
+            push_branch(config, &repo, branch)?;
+        }
+    }
+
+    if let (Some(publish_dir), Some(files)) = (&config.publish_dir, &deployment.files) {
+        check_publish_dir(config, Path::new(publish_dir))?;
+        sync_directory(config, Path::new(publish_dir), files)?;
+    }
+
+    add_deployment(config, deployment.source_commit, branch_commit, deployment.files, Some(id))
+}
+
+fn add_deployment(
+    config: &Config,
+    source_commit: Option<String>,
+    branch_commit: Option<String>,
+    files: Option<BTreeMap<String, String>>,
+    rollback_of: Option<u64>,
+) -> Result<Deployment, ApiError> {
+    let mut deployments = get_deployments(config)?;
+    let deployment = Deployment {
+        id: deployments.iter().map(|deployment| deployment.id).max().unwrap_or(0) + 1,
+        time: match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs(),
+            Err(_) => 0,
+        },
+        source_commit,
+        branch_commit,
+        files,
+        rollback_of,
+    };
+    deployments.push(deployment.clone());
+    if deployments.len() > MAX_DEPLOYMENTS {
+        deployments.drain(..deployments.len() - MAX_DEPLOYMENTS);
+    }
+
+    let content = match serde_json::to_string_pretty(&deployments) {
+        Ok(content) => content,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to serialize deployments: {}", e)));
+        }
+    };
+    if let Err(e) = fs::create_dir_all(&config.data_path).and_then(|_| fs::write(config.get_deployments_path(), content)) {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to write deployments: {}", e)));
+    }
+
+    collect_objects(config, &deployments)?;
+    Ok(deployment)
+}
+
+// removes stored contents no deployment refers to anymore, e.g. of deployments to a branch only
+fn collect_objects(config: &Config, deployments: &[Deployment]) -> Result<(), ApiError> {
+    let referenced: HashSet<&String> = deployments
+        .iter()
+        .filter_map(|deployment| deployment.files.as_ref())
+        .flat_map(|files| files.values())
+        .collect();
+
+    let entries = match fs::read_dir(config.get_deploy_objects_path()) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read deploy objects: {}", e)));
+        }
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if referenced.contains(&name) {
+            continue;
+        }
+        if let Err(e) = fs::remove_file(entry.path()) {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to remove deploy object {}: {}", name, e)));
+        }
+    }
+    Ok(())
+}
+
+// returns (relative name, absolute path) of all files of the public site
+fn find_site_files(config: &Config) -> Vec<(String, PathBuf)> {
+    let working_path = Path::new(config.working_path.as_str());
+    let mut files = vec![];
+    for publish_path in PUBLISH_PATHS {
+        for file in WalkDir::new(working_path.join(publish_path))
+            .into_iter()
+            .filter_entry(|e| !e.file_name().to_string_lossy().starts_with("."))
+            .filter_map(|e| e.ok())
+        {
+            if file.file_type().is_dir() {
+                continue;
+            }
+            let name = match file.path().strip_prefix(working_path) {
+                Ok(name) => name.to_string_lossy().to_string(),
+                Err(_) => continue,
+            };
+            files.push((name, file.path().to_path_buf()));
+        }
+    }
+    files
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, ApiError> {
+    match fs::read(path) {
+        Ok(content) => Ok(content),
+        Err(e) => Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read {}: {}", path.to_string_lossy(), e))),
+    }
+}
+
+fn checksum(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+// keeps every deployed file content by checksum, so directory deployments can be rolled back
+fn store_object(config: &Config, content: &[u8]) -> Result<String, ApiError> {
+    let checksum = checksum(content);
+    let objects_path = config.get_deploy_objects_path();
+    let object_path = objects_path.join(checksum.as_str());
+    if !object_path.exists() {
+        if let Err(e) = fs::create_dir_all(&objects_path).and_then(|_| fs::write(&object_path, content)) {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to store deploy object: {}", e)));
+        }
+    }
+    Ok(checksum)
+}
+
+// the deploy directory is emptied of everything not part of the site,
+// so it must not overlap the working path with the posts and the generated site, or the data path
+fn check_publish_dir(config: &Config, publish_dir: &Path) -> Result<(), ApiError> {
+    let publish_dir = absolute_path(publish_dir);
+    let protected_paths = [
+        PathBuf::from(config.working_path.as_str()),
+        config.get_input_path(),
+        config.get_output_path(),
+        PathBuf::from(config.data_path.as_str()),
+    ];
+    for protected_path in protected_paths.iter() {
+        let protected_path = absolute_path(protected_path);
+        if publish_dir.starts_with(&protected_path) || protected_path.starts_with(&publish_dir) {
+            return Err(ApiError::new(
+                StatusCode::PreconditionFailed,
+                format!(
+                    "PUBLISH_DIR {} overlaps {}",
+                    publish_dir.to_string_lossy(),
+                    protected_path.to_string_lossy()
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// resolves symlinks of the existing part of a path, which may not exist yet
+fn absolute_path(path: &Path) -> PathBuf {
+    let mut existing = path.to_path_buf();
+    let mut rest = vec![];
+    while !existing.exists() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent.to_path_buf();
+            }
+            _ => break,
+        }
+    }
+    let mut path = fs::canonicalize(&existing).unwrap_or(existing);
+    for name in rest.iter().rev() {
+        path.push(name);
+    }
+    path
+}
+
+fn sync_directory(config: &Config, publish_dir: &Path, files: &BTreeMap<String, String>) -> Result<(), ApiError> {
+    for (name, file_checksum) in files.iter() {
+        let target = publish_dir.join(name);
+        if target.exists() && checksum(&read_file(&target)?) == *file_checksum {
+            continue;
+        }
+        let content = read_file(&config.get_deploy_objects_path().join(file_checksum))?;
+        let result = match target.parent() {
+            Some(parent) => fs::create_dir_all(parent).and_then(|_| fs::write(&target, content)),
+            None => fs::write(&target, content),
+        };
+        if let Err(e) = result {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to write {}: {}", target.to_string_lossy(), e)));
+        }
+    }
+
+    // remove files which are no longer part of the site
+    for file in WalkDir::new(publish_dir).contents_first(true).into_iter().filter_map(|e| e.ok()) {
+        let name = match file.path().strip_prefix(publish_dir) {
+            Ok(name) => name.to_string_lossy().to_string(),
+            Err(_) => continue,
+        };
+        if name.is_empty() {
+            continue;
+        }
+        if file.file_type().is_dir() {
+            // only succeeds for directories left empty
+            let _ = fs::remove_dir(file.path());
+        } else if !files.contains_key(&name) {
+            if let Err(e) = fs::remove_file(file.path()) {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to remove {}: {}", name, e)));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn open_publish_repo(config: &Config) -> Result<Repository, ApiError> {
+    match Repository::open(config.get_publish_repo_path()) {
+        Ok(repo) => Ok(repo),
+        Err(e) => Err(ApiError::new(StatusCode::InternalServerError, format!("failed to open publish repository: {}", e.message()))),
+    }
+}
+
+fn commit_to_branch(
+    config: &Config,
+    branch: &str,
+    files: &[(String, PathBuf)],
+    message: &str,
+    push: bool,
+) -> Result<String, ApiError> {
+    let repo = open_publish_repo(config)?;
+
+    // build the tree in a standalone index, so neither HEAD nor the working tree are touched
+    let mut index = match Index::new() {
+        Ok(index) => index,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to create index: {}", e.message())));
+        }
+    };
+    for (name, path) in files.iter() {
+        let content = read_file(path)?;
+        let blob_id = match repo.blob(&content) {
+            Ok(blob_id) => blob_id,
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to write blob for {}: {}", name, e.message())));
+            }
+        };
+        let entry = new_index_entry(name.as_str(), blob_id, content.len() as u32);
+        if let Err(e) = index.add(&entry) {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to add {}: {}", name, e.message())));
+        }
+    }
+    let tree = match index.write_tree_to(&repo).and_then(|oid| repo.find_tree(oid)) {
+        Ok(tree) => tree,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("could not write tree: {}", e.message())));
+        }
+    };
+
+    let commit_id = commit_tree(&repo, branch, &tree, message)?;
+    if push {
+        push_branch(config, &repo, branch)?;
+    }
+    Ok(commit_id)
+}
+
+fn commit_tree(repo: &Repository, branch: &str, tree: &git2::Tree, message: &str) -> Result<String, ApiError> {
+    let signature = match repo.signature() {
+        Ok(signature) => signature,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("missing signature: {}", e.message())));
+        }
+    };
+
+    let ref_name = format!("refs/heads/{}", branch);
+    let parent = match repo.find_reference(ref_name.as_str()) {
+        Ok(reference) => match reference.peel_to_commit() {
+            Ok(commit) => Some(commit),
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to find branch commit: {}", e.message())));
+            }
+        },
+        Err(_) => None,
+    };
+
+    // nothing changed since the last deployment
+    if let Some(parent) = &parent {
+        if parent.tree_id() == tree.id() {
+            return Ok(parent.id().to_string());
+        }
+    }
+
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    match repo.commit(Some(ref_name.as_str()), &signature, &signature, message, tree, &parents) {
+        Ok(commit_id) => Ok(commit_id.to_string()),
+        Err(e) => Err(ApiError::new(StatusCode::InternalServerError, format!("unable to commit: {}", e.message()))),
+    }
+}
+
+fn push_branch(config: &Config, repo: &Repository, branch: &str) -> Result<(), ApiError> {
+    let mut remote = match repo.find_remote(REMOTE_NAME) {
+        Ok(remote) => remote,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to find remote: {}", e.message())));
+        }
+    };
+
+    let mut push_option = PushOptions::new();
+    push_option.remote_callbacks(get_remote_callbacks(config));
+    let ref_name = format!("refs/heads/{}", branch);
+    if let Err(e) = remote.push(&[ref_name.as_str()], Some(&mut push_option)) {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to push to remote: {}", e.message())));
+    }
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use crate::blog::error::GeneratorError;
+use crate::blog::utils::join_relative;
+use git2::{DiffFindOptions, Delta, Oid, Repository, Sort};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tera::escape_html;
+
+// explicitly declared redirects in the posts folder, one "old new" pair per line
+pub const REDIRECTS_FILE: &str = "redirects.txt";
+// exports for web servers, next to the generated pages
+const NETLIFY_EXPORT_FILE: &str = "_redirects";
+const NGINX_EXPORT_FILE: &str = "redirects.map";
+// the generated pages are served below this url path
+const OUTPUT_URL_PATH: &str = "/p/";
+
+// (old page, new page) pairs
+type Renames = Vec<(String, String)>;
+
+// renames found in the history up to a commit, per posts folder
+static RENAME_CACHE: OnceLock<Mutex<HashMap<PathBuf, (Oid, Renames)>>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Redirect {
+    pub from: String,
+    pub to: String,
+    pub declared: bool,
+}
+
+// builds the map of old page urls (relative to the output path) to their new url,
+// from renames of posts in the git history and the redirects file, which has precedence
+pub fn get_redirects(input_path: &PathBuf) -> Result<Vec<Redirect>, GeneratorError> {
+    let mut redirects: BTreeMap<String, Redirect> = BTreeMap::new();
+
+    for (from, to) in get_renamed_posts(input_path)? {
+        // follow chains of renames, so every old url points to the latest one
+        for redirect in redirects.values_mut() {
+            if redirect.to == from {
+                redirect.to = to.clone();
+            }
+        }
+        redirects.insert(from.clone(), Redirect { from, to, declared: false });
+    }
+
+    let redirects_path = input_path.join(REDIRECTS_FILE);
+    if redirects_path.exists() {
+        let content = match fs::read_to_string(&redirects_path) {
+            Ok(content) => content,
+            Err(e) => {
+                return Err(GeneratorError::new(format!("unable to read {}: {}", REDIRECTS_FILE, e)));
+            }
+        };
+        for (idx, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_whitespace().collect::<Vec<&str>>().as_slice() {
+                // stubs are written to the old urls, which have to stay below the output path
+                [from, to] if is_external(from) || join_relative(input_path, from).is_none() => {
+                    return Err(GeneratorError::new(format!(
+                        "invalid redirect source in {} line {}: {}",
+                        REDIRECTS_FILE,
+                        idx + 1,
+                        from
+                    )));
+                }
+                [from, to] => {
+                    let from = to_page(from);
+                    redirects.insert(from.clone(), Redirect { from, to: to_page(to), declared: true });
+                }
+                _ => {
+                    return Err(GeneratorError::new(format!(
+                        "invalid redirect in {} line {}: {}",
+                        REDIRECTS_FILE,
+                        idx + 1,
+                        line
+                    )));
+                }
+            }
+        }
+    }
+
+    // pages that exist (again) are never redirected, renamed posts that were removed later are gone
+    let page_exists = |page: &str| input_path.join(page.replace(".html", ".md")).exists();
+    Ok(redirects
+        .into_values()
+        .filter(|redirect| redirect.from != redirect.to && !page_exists(redirect.from.as_str()))
+        .filter(|redirect| redirect.declared || page_exists(redirect.to.as_str()))
+        .collect())
+}
+
+// writes a stub page for every old url and the exports, returns the number of stubs
+pub fn write_redirects(output_path: &Path, redirects: &Vec<Redirect>) -> Result<usize, GeneratorError> {
+    let mut netlify_export = String::new();
+    let mut nginx_export = String::new();
+    let mut count = 0;
+
+    for redirect in redirects {
+        let target_url = if is_external(redirect.to.as_str()) {
+            redirect.to.clone()
+        } else {
+            format!("{}{}", OUTPUT_URL_PATH, redirect.to)
+        };
+        netlify_export.push_str(format!("{}{} {} 301\n", OUTPUT_URL_PATH, redirect.from, target_url).as_str());
+        nginx_export.push_str(format!("{}{} {};\n", OUTPUT_URL_PATH, redirect.from, target_url).as_str());
+
+        let stub_path = match join_relative(output_path, redirect.from.as_str()) {
+            Some(stub_path) => stub_path,
+            None => {
+                return Err(GeneratorError::new(format!("invalid redirect source: {}", redirect.from)));
+            }
+        };
+        if stub_path.exists() {
+            continue;
+        }
+        if let Some(parent) = stub_path.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                return Err(GeneratorError::new(format!("unable to create {}: {}", parent.to_string_lossy(), e)));
+            }
+        }
+        if let Err(e) = fs::write(&stub_path, redirect_stub(redirect)) {
+            return Err(GeneratorError::new(format!("unable to write {}: {}", stub_path.to_string_lossy(), e)));
+        }
+        count += 1;
+    }
+
+    for (file, content) in [(NETLIFY_EXPORT_FILE, netlify_export), (NGINX_EXPORT_FILE, nginx_export)] {
+        if let Err(e) = fs::write(output_path.join(file), content) {
+            return Err(GeneratorError::new(format!("unable to write {}: {}", file, e)));
+        }
+    }
+
+    Ok(count)
+}
+
+// renames of markdown files in the history of the posts repository, oldest first,
+// only the commits since the last call are diffed while the history is only extended
+fn get_renamed_posts(input_path: &PathBuf) -> Result<Renames, GeneratorError> {
+    // without a repository there is no history to follow
+    let repo = match Repository::open(input_path) {
+        Ok(repo) => repo,
+        Err(_) => return Ok(vec![]),
+    };
+    let head = match repo.head().and_then(|head| head.peel_to_commit()) {
+        Ok(commit) => commit.id(),
+        Err(_) => return Ok(vec![]),
+    };
+
+    let to_error = |e: git2::Error| GeneratorError::new(format!("unable to read history: {}", e.message()));
+    let cache = RENAME_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    let (cached_head, mut renames) = match cache.lock().unwrap().get(input_path) {
+        Some((cached_head, renames)) if *cached_head == head => return Ok(renames.clone()),
+        Some((cached_head, renames)) if repo.graph_descendant_of(head, *cached_head).unwrap_or(false) => {
+            (Some(*cached_head), renames.clone())
+        }
+        _ => (None, vec![]),
+    };
+
+    let mut revwalk = repo.revwalk().map_err(to_error)?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE).map_err(to_error)?;
+    revwalk.push(head).map_err(to_error)?;
+    if let Some(cached_head) = cached_head {
+        revwalk.hide(cached_head).map_err(to_error)?;
+    }
+
+    for oid in revwalk {
+        let commit = repo.find_commit(oid.map_err(to_error)?).map_err(to_error)?;
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree().map_err(to_error)?),
+            Err(_) => None,
+        };
+        let tree = commit.tree().map_err(to_error)?;
+        let mut diff = repo
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+            .map_err(to_error)?;
+        diff.find_similar(Some(DiffFindOptions::new().renames(true))).map_err(to_error)?;
+
+        for delta in diff.deltas() {
+            if delta.status() != Delta::Renamed {
+                continue;
+            }
+            let old_path = delta.old_file().path().map(|p| p.to_string_lossy().to_string());
+            let new_path = delta.new_file().path().map(|p| p.to_string_lossy().to_string());
+            if let (Some(old_path), Some(new_path)) = (old_path, new_path) {
+                if old_path.ends_with(".md") && new_path.ends_with(".md") {
+                    renames.push((to_page(old_path.as_str()), to_page(new_path.as_str())));
+                }
+            }
+        }
+    }
+
+    cache.lock().unwrap().insert(input_path.clone(), (head, renames.clone()));
+    Ok(renames)
+}
+
+fn redirect_stub(redirect: &Redirect) -> String {
+    let url = if is_external(redirect.to.as_str()) {
+        redirect.to.clone()
+    } else {
+        // stubs in sub folders have to climb up to the output path
+        let depth = Path::new(redirect.from.as_str()).components().count() - 1;
+        format!("{}{}", "../".repeat(depth), redirect.to)
+    };
+    // declared targets end up in attributes
+    let url = escape_html(url.as_str());
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Redirecting…</title>\n\
+         <link rel=\"canonical\" href=\"{url}\">\n<meta http-equiv=\"refresh\" content=\"0; url={url}\">\n\
+         </head>\n<body>\n<a href=\"{url}\">{url}</a>\n</body>\n</html>\n",
+        url = url
+    )
+}
+
+// redirects may be declared with the markdown or the page name
+fn to_page(path: &str) -> String {
+    match path.strip_suffix(".md") {
+        Some(base_name) if !is_external(path) => format!("{}.html", base_name),
+        _ => path.to_string(),
+    }
+}
+
+fn is_external(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
@@ -0,0 +1,211 @@
+use crate::blog::config::{Config, DEFAULT_BRANCH, REF_NAME, REMOTE_NAME, TRACKING_REF_NAME};
+use crate::blog::error::ApiError;
+use crate::blog::utils::{fetch_remote, get_changed_paths, get_changes, get_remote_callbacks};
+use git2::{ErrorCode, Oid, PushOptions, Repository, StashApplyOptions, StashFlags};
+use tide::StatusCode;
+
+// fetches the remote and fast-forwards the default branch,
+// local changes are stashed and reapplied unless they conflict with the remote changes
+pub fn pull_remote(config: &Config) -> Result<String, ApiError> {
+    let repo_path = config.get_input_path();
+    let mut repo = match Repository::open(repo_path) {
+        Ok(repo) => repo,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("failed to open: {}", e.message())));
+        }
+    };
+
+    if let Err(e) = fetch_remote(config, &repo) {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to pull from remote: {}", e.message())));
+    }
+
+    let fetch_head = match repo.find_reference(TRACKING_REF_NAME) {
+        Ok(fetch_head) => fetch_head,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to find {}: {}", TRACKING_REF_NAME, e.message())));
+        }
+    };
+    let fetch_commit = match repo.reference_to_annotated_commit(&fetch_head) {
+        Ok(fetch_commit) => fetch_commit,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to find fetch commit: {}", e.message())));
+        }
+    };
+
+    let merge_analysis = match repo.merge_analysis(&[&fetch_commit]) {
+        Ok(merge_analysis) => merge_analysis,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to find merge analysis: {}", e.message())));
+        }
+    };
+
+    let fetch_oid = fetch_commit.id();
+    let is_up_to_date = merge_analysis.0.is_up_to_date();
+    let is_fast_forward = merge_analysis.0.is_fast_forward();
+
+    // release borrows on the repository, stashing requires mutable access
+    drop(fetch_commit);
+    drop(fetch_head);
+
+    if is_up_to_date {
+        Ok("Already up to date".to_string())
+    } else if is_fast_forward {
+        println!("Fast-forwarding");
+        let ref_name = format!("refs/heads/{}", DEFAULT_BRANCH);
+
+        // refuse if local changes touch files changed on the remote, otherwise stash them
+        let changes = get_changes(&repo);
+        let mut stashed: Option<Oid> = None;
+        if !changes.is_empty() {
+            let remote_paths = match get_changed_paths(&repo, &ref_name, fetch_oid) {
+                Ok(remote_paths) => remote_paths,
+                Err(e) => {
+                    return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to diff remote: {}", e.message())));
+                }
+            };
+            let mut conflicting: Vec<String> = vec![];
+            for change in changes.iter() {
+                let mut names = vec![&change.name];
+                if let Some(old_name) = change.old_name.as_ref() {
+                    names.push(old_name);
+                }
+                for name in names {
+                    if remote_paths.contains(name) && !conflicting.contains(name) {
+                        conflicting.push(name.clone());
+                    }
+                }
+            }
+            if !conflicting.is_empty() {
+                return Err(ApiError::new(StatusCode::Conflict, format!(
+                    "local changes conflict with remote changes: {}", conflicting.join(", "))));
+            }
+
+            let signature = match repo.signature() {
+                Ok(signature) => signature,
+                Err(e) => {
+                    return Err(ApiError::new(StatusCode::InternalServerError, format!("missing signature: {}", e)));
+                }
+            };
+            match repo.stash_save(&signature, "auto-stash before pull", Some(StashFlags::INCLUDE_UNTRACKED)) {
+                Ok(stash_oid) => stashed = Some(stash_oid),
+                Err(e) if e.code() == ErrorCode::NotFound => {}
+                Err(e) => {
+                    return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to stash local changes: {}", e.message())));
+                }
+            }
+        }
+
+        let mut reference = match repo.find_reference(&ref_name) {
+            Ok(reference) => reference,
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to find reference: {}", e.message())));
+            }
+        };
+        match reference.set_target(fetch_oid, "Fast-Forward") {
+            Ok(_) => {}
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to set target: {}", e.message())));
+            }
+        }
+        drop(reference);
+        match repo.set_head(&ref_name) {
+            Ok(_) => {}
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to set head: {}", e.message())));
+            }
+        };
+        match repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force())) {
+            Ok(_) => {}
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to checkout head: {}", e.message())));
+            }
+        };
+
+        let mut message = "Fast-forwarded".to_string();
+        if let Some(stash_oid) = stashed {
+            // on failure the stash is kept, so no work is lost
+            let index = match find_stash_index(&mut repo, stash_oid) {
+                Some(index) => index,
+                None => {
+                    return Err(ApiError::new(StatusCode::Conflict, format!(
+                        "fast-forwarded, but the stash {} with the local changes is missing", stash_oid)));
+                }
+            };
+            let mut stash_options = StashApplyOptions::new();
+            // restores what was staged, not only the working tree
+            stash_options.reinstantiate_index();
+            if let Err(e) = repo.stash_pop(index, Some(&mut stash_options)) {
+                return Err(ApiError::new(StatusCode::Conflict, format!(
+                    "fast-forwarded, but local changes could not be reapplied and remain in stash@{{{}}}: {}", index, e.message())));
+            }
+            message.push_str(", local changes reapplied");
+        }
+
+        Ok(message)
+    } else {
+        Err(ApiError::new(StatusCode::InternalServerError, String::from("Merge needed")))
+    }
+}
+
+// the position of the stash in the stash list, other stashes may have been added meanwhile
+fn find_stash_index(repo: &mut Repository, stash_oid: Oid) -> Option<usize> {
+    let mut found = None;
+    let _ = repo.stash_foreach(|index, _message, oid| {
+        if *oid == stash_oid {
+            found = Some(index);
+            return false;
+        }
+        true
+    });
+    found
+}
+
+// pushes the default branch to the remote and updates the remote tracking branch
+pub fn push_remote(config: &Config) -> Result<(), ApiError> {
+    let repo_path = config.get_input_path();
+    let repo = match Repository::open(repo_path) {
+        Ok(repo) => repo,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("failed to open: {}", e.message())));
+        }
+    };
+
+    let mut remote = match repo.find_remote(REMOTE_NAME) {
+        Ok(remote) => remote,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to find remote: {}", e.message())));
+        }
+    };
+
+    let local_oid = match repo.refname_to_id(REF_NAME) {
+        Ok(local_oid) => local_oid,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to find {}: {}", REF_NAME, e.message())));
+        }
+    };
+
+    // the remote may reject the update without failing the push
+    let mut rejected: Option<String> = None;
+    let mut callbacks = get_remote_callbacks(config);
+    callbacks.push_update_reference(|_refname, status| {
+        if let Some(status) = status {
+            rejected = Some(status.to_string());
+        }
+        Ok(())
+    });
+    let mut push_option = PushOptions::new();
+    push_option.remote_callbacks(callbacks);
+    if let Err(e) = remote.push(&[REF_NAME], Some(&mut push_option)) {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to push to remote: {}", e.message())));
+    }
+    drop(push_option);
+    if let Some(status) = rejected {
+        return Err(ApiError::new(StatusCode::Conflict, format!("push rejected by remote: {}", status)));
+    }
+
+    if let Err(e) = repo.reference(TRACKING_REF_NAME, local_oid, true, "push") {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to update {}: {}", TRACKING_REF_NAME, e.message())));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use crate::blog::config::Config;
+use crate::blog::error::ApiError;
+use crate::blog::generator::Generator;
+use crate::blog::utils::{find_files, get_head_files, get_staged_files};
+use git2::Repository;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tera::Tera;
+use tide::StatusCode;
+
+const MAX_RESULTS: usize = 500;
+// characters of context around the matches of a line
+const SNIPPET_CONTEXT: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSource {
+    WorkingTree,
+    Staged,
+    Head,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    // all terms have to occur in a post, "quoted phrases" are one term
+    Text,
+    Regex,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    #[serde(default = "default_mode")]
+    pub mode: SearchMode,
+    #[serde(default = "default_source")]
+    pub source: SearchSource,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    pub tag: Option<String>,
+    pub status: Option<String>,
+}
+
+fn default_mode() -> SearchMode {
+    SearchMode::Text
+}
+
+fn default_source() -> SearchSource {
+    SearchSource::WorkingTree
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchMatch {
+    pub file: String,
+    pub line: usize,
+    pub snippet: String,
+    // (start, end) character offsets of the matches in the snippet
+    pub highlights: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResult {
+    pub matches: Vec<SearchMatch>,
+    pub truncated: bool,
+}
+
+pub fn search_posts(config: &Config, query: &SearchQuery) -> Result<SearchResult, ApiError> {
+    let terms = match query.mode {
+        SearchMode::Text => split_terms(query.q.as_str()).iter().map(|term| regex::escape(term)).collect(),
+        SearchMode::Regex => vec![query.q.clone()],
+    };
+    if terms.is_empty() {
+        return Err(ApiError::new(StatusCode::BadRequest, String::from("empty query")));
+    }
+    let term_regexes = terms
+        .iter()
+        .map(|term| build_regex(term.as_str(), query.case_sensitive))
+        .collect::<Result<Vec<Regex>, ApiError>>()?;
+    let line_regex = build_regex(terms.join("|").as_str(), query.case_sensitive)?;
+
+    let tera = Tera::default();
+    let generator = Generator::new(&tera, config.get_input_path(), config.get_output_path(), None);
+
+    let mut sources = get_sources(config, query.source)?;
+    sources.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut matches = vec![];
+    for (file, content) in sources.iter() {
+        if !term_regexes.iter().all(|regex| regex.is_match(content)) {
+            continue;
+        }
+
+        if query.tag.is_some() || query.status.is_some() {
+            // posts that can't be parsed have no metadata to match
+            let post = match generator.new_post(file.clone(), &mut content.clone()) {
+                Ok(post) => post,
+                Err(_) => continue,
+            };
+            if let Some(tag) = &query.tag {
+                if !post.get_tags().contains(tag) {
+                    continue;
+                }
+            }
+            if let Some(status) = &query.status {
+                if post.get_status() != Some(status) {
+                    continue;
+                }
+            }
+        }
+
+        for (idx, line) in content.lines().enumerate() {
+            let ranges: Vec<(usize, usize)> = line_regex
+                .find_iter(line)
+                .filter(|m| m.start() != m.end())
+                .map(|m| (m.start(), m.end()))
+                .collect();
+            if ranges.is_empty() {
+                continue;
+            }
+            if matches.len() == MAX_RESULTS {
+                return Ok(SearchResult { matches, truncated: true });
+            }
+            let (snippet, highlights) = get_snippet(line, &ranges);
+            matches.push(SearchMatch {
+                file: file.clone(),
+                line: idx + 1,
+                snippet,
+                highlights,
+            });
+        }
+    }
+
+    Ok(SearchResult { matches, truncated: false })
+}
+
+fn get_sources(config: &Config, source: SearchSource) -> Result<Vec<(String, String)>, ApiError> {
+    if source == SearchSource::WorkingTree {
+        let mut sources = vec![];
+        for file in find_files(&config.get_input_path(), Some(".md")) {
+            if file.is_dir {
+                continue;
+            }
+            match fs::read_to_string(config.get_input_path().join(file.name.as_str())) {
+                Ok(content) => sources.push((file.name, content)),
+                Err(e) => {
+                    return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read {}: {}", file.name, e)));
+                }
+            }
+        }
+        return Ok(sources);
+    }
+
+    let repo = match Repository::open(config.get_input_path()) {
+        Ok(repo) => repo,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("failed to open: {}", e.message())));
+        }
+    };
+    let sources = match source {
+        SearchSource::Staged => get_staged_files(&repo).map(|(sources, _)| sources),
+        _ => get_head_files(&repo),
+    };
+    match sources {
+        Ok(sources) => Ok(sources),
+        Err(e) => Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read posts: {}", e.message()))),
+    }
+}
+
+// splits on whitespace, except within double quotes
+fn split_terms(query: &str) -> Vec<String> {
+    let mut terms = vec![];
+    for (idx, part) in query.split('"').enumerate() {
+        if idx % 2 == 1 {
+            if !part.trim().is_empty() {
+                terms.push(part.to_string());
+            }
+        } else {
+            terms.extend(part.split_whitespace().map(String::from));
+        }
+    }
+    terms
+}
+
+fn build_regex(pattern: &str, case_sensitive: bool) -> Result<Regex, ApiError> {
+    match RegexBuilder::new(pattern).case_insensitive(!case_sensitive).build() {
+        Ok(regex) => Ok(regex),
+        Err(e) => Err(ApiError::new(StatusCode::BadRequest, format!("invalid query: {}", e))),
+    }
+}
+
+// cuts the line around the matches, `ranges` are byte offsets into the line
+fn get_snippet(line: &str, ranges: &[(usize, usize)]) -> (String, Vec<(usize, usize)>) {
+    let to_chars = |byte_offset: usize| line[..byte_offset].chars().count();
+    let first = to_chars(ranges.first().unwrap().0);
+    let last = to_chars(ranges.last().unwrap().1);
+    let start = first.saturating_sub(SNIPPET_CONTEXT);
+    let end = last + SNIPPET_CONTEXT;
+
+    let snippet: String = line.chars().skip(start).take(end - start).collect();
+    let highlights = ranges
+        .iter()
+        .map(|(match_start, match_end)| (to_chars(*match_start) - start, to_chars(*match_end) - start))
+        .collect();
+    (snippet, highlights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_words() {
+        assert_eq!(split_terms("  foo bar\tbaz "), vec!["foo", "bar", "baz"]);
+        assert!(split_terms("   ").is_empty());
+    }
+
+    #[test]
+    fn keeps_quoted_phrases() {
+        assert_eq!(split_terms(r#"foo "bar  baz" qux"#), vec!["foo", "bar  baz", "qux"]);
+        assert_eq!(split_terms(r#""bar baz"qux"#), vec!["bar baz", "qux"]);
+        assert_eq!(split_terms(r#"foo "" "  " bar"#), vec!["foo", "bar"]);
+        // an unterminated quote runs until the end
+        assert_eq!(split_terms(r#"foo "bar baz"#), vec!["foo", "bar baz"]);
+    }
+}
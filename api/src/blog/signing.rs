@@ -0,0 +1,89 @@
+use crate::blog::config::{Config, SigningFormat};
+use std::fmt;
+use std::fmt::Display;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone)]
+pub struct SigningError {
+    pub message: String,
+}
+
+impl Display for SigningError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl SigningError {
+    pub fn new(message: String) -> SigningError {
+        SigningError {
+            message,
+        }
+    }
+}
+
+// signs the commit buffer with the configured key,
+// returns None if no key is configured and signing is not required
+pub fn sign_commit_buffer(config: &Config, buffer: &str) -> Result<Option<String>, SigningError> {
+    let signing_key = match &config.signing_key {
+        Some(signing_key) => signing_key,
+        None => {
+            if config.require_signing {
+                return Err(SigningError::new(String::from(
+                    "commit signing is required, but no SIGNING_KEY is configured",
+                )));
+            }
+            return Ok(None);
+        }
+    };
+
+    let mut command = match config.signing_format {
+        SigningFormat::Ssh => {
+            if !Path::new(signing_key).exists() {
+                return Err(SigningError::new(format!("ssh signing key not found: {}", signing_key)));
+            }
+            let mut command = Command::new("ssh-keygen");
+            command.arg("-Y").arg("sign").arg("-n").arg("git").arg("-f").arg(signing_key);
+            command
+        }
+        SigningFormat::Gpg => {
+            let mut command = Command::new("gpg");
+            command.arg("--batch").arg("--armor").arg("--detach-sign").arg("--local-user").arg(signing_key);
+            command
+        }
+    };
+
+    let mut child = match command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => return Err(SigningError::new(format!("unable to start signing program: {}", e))),
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        if let Err(e) = stdin.write_all(buffer.as_bytes()) {
+            return Err(SigningError::new(format!("unable to pass commit to signing program: {}", e)));
+        }
+    }
+
+    let output = match child.wait_with_output() {
+        Ok(output) => output,
+        Err(e) => return Err(SigningError::new(format!("signing program failed: {}", e))),
+    };
+    if !output.status.success() {
+        return Err(SigningError::new(format!(
+            "unable to sign commit: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    match String::from_utf8(output.stdout) {
+        Ok(signature) => Ok(Some(signature)),
+        Err(e) => Err(SigningError::new(format!("invalid signature: {}", e))),
+    }
+}
@@ -0,0 +1,138 @@
+use crate::blog::config::Config;
+use crate::blog::error::ApiError;
+use crate::blog::generator::Generator;
+use crate::blog::posts::PostPaths;
+use crate::blog::utils::join_relative;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tera::Tera;
+use tide::StatusCode;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatusTransition {
+    pub time: u64,
+    pub file: String,
+    // none when the post had no status or it was removed
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub user: String,
+}
+
+// sets (or removes) the status attribute of the post and records the transition
+pub fn change_status(config: &Config, file: &str, to: Option<&str>, user: &str) -> Result<StatusTransition, ApiError> {
+    let post = PostPaths::find(config, file)?;
+    // new statuses always have to be known, also with the default statuses
+    if let Some(to) = to {
+        if config.get_status(to).is_none() {
+            return Err(ApiError::new(StatusCode::UnprocessableEntity, format!("unknown status: {}", to)));
+        }
+    }
+
+    let path = match join_relative(&config.get_input_path(), post.file.as_str()) {
+        Some(path) => path,
+        None => {
+            return Err(ApiError::new(StatusCode::BadRequest, format!("invalid post path: {}", post.file)));
+        }
+    };
+    let mut content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read {}: {}", post.file, e)));
+        }
+    };
+    let tera = Tera::default();
+    let generator = Generator::new(&tera, config.get_input_path(), config.get_output_path(), None);
+    let attributes = match generator.scan_attributes(&post.file, content.as_str()) {
+        Ok(attributes) => attributes,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::UnprocessableEntity, e.message));
+        }
+    };
+    let statuses: Vec<_> = attributes.iter().filter(|(_, name, _)| name == "status").collect();
+    if statuses.len() > 1 {
+        return Err(ApiError::new(StatusCode::UnprocessableEntity, format!("multiple statuses in {}", post.file)));
+    }
+    let current = statuses.first().map(|(range, _, value)| (range.clone(), value.clone()));
+
+    let from = current.as_ref().map(|(_, value)| value.as_str());
+    if from == to {
+        return Err(ApiError::new(StatusCode::BadRequest, format!("status is already {}", to.unwrap_or("unset"))));
+    }
+    // unknown statuses (e.g. typos) may always be corrected
+    let known_from = from.filter(|from| config.get_status(from).is_some());
+    if !config.is_transition_allowed(known_from, to) {
+        return Err(ApiError::new(
+            StatusCode::Conflict,
+            format!("status transition from {} to {} is not allowed", from.unwrap_or("unset"), to.unwrap_or("unset")),
+        ));
+    }
+
+    match (&current, to) {
+        (Some((range, _)), Some(to)) => content.replace_range(range.clone(), format!("[status:{}]", to).as_str()),
+        (Some((range, _)), None) => {
+            let end = if content[range.end..].starts_with('\n') { range.end + 1 } else { range.end };
+            content.replace_range(range.start..end, "");
+        }
+        (None, Some(to)) => {
+            // below the other attributes, or else below the title
+            let pos = match attributes.last() {
+                Some((range, _, _)) => range.end,
+                None if content.starts_with('#') => content.find('\n').unwrap_or(content.len()),
+                None => 0,
+            };
+            if pos == 0 {
+                content.insert_str(0, format!("[status:{}]\n", to).as_str());
+            } else {
+                content.insert_str(pos, format!("\n[status:{}]", to).as_str());
+            }
+        }
+        (None, None) => {}
+    }
+    if let Err(e) = fs::write(&path, content.as_bytes()) {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to write {}: {}", post.file, e)));
+    }
+
+    let transition = StatusTransition {
+        time: match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs(),
+            Err(_) => 0,
+        },
+        file: post.file,
+        from: from.map(String::from),
+        to: to.map(String::from),
+        user: user.to_string(),
+    };
+    if let Err(e) = append_status_log(config, &transition) {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to record transition: {}", e)));
+    }
+    Ok(transition)
+}
+
+// recorded transitions, oldest first, optionally of one post only
+pub fn get_status_transitions(config: &Config, file: Option<&str>) -> Result<Vec<StatusTransition>, ApiError> {
+    let log = match fs::read_to_string(config.get_status_log_path()) {
+        Ok(log) => log,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read status log: {}", e)));
+        }
+    };
+    Ok(log
+        .lines()
+        .filter_map(|line| serde_json::from_str::<StatusTransition>(line).ok())
+        .filter(|transition| file.is_none_or(|file| transition.file == file))
+        .collect())
+}
+
+fn append_status_log(config: &Config, transition: &StatusTransition) -> Result<(), std::io::Error> {
+    let path = config.get_status_log_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let line = serde_json::to_string(transition)?;
+    file.write_all(format!("{}\n", line).as_bytes())
+}
@@ -0,0 +1,223 @@
+use crate::blog::config::Config;
+use crate::blog::error::ApiError;
+use crate::blog::generator::Generator;
+use crate::blog::trash::{move_to_trash, TrashItem};
+use crate::blog::utils::find_files;
+use git2::Patch;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use tera::Tera;
+use tide::StatusCode;
+
+#[derive(Debug, Serialize)]
+pub struct TagInfo {
+    pub name: String,
+    pub count: usize,
+    pub posts: Vec<String>,
+    // the overview image of the tag, if there is one
+    pub cutout: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagRewrite {
+    pub file: String,
+    // unified diff of the post
+    pub diff: String,
+    #[serde(skip)]
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CutoutChange {
+    pub from: String,
+    // none when the image is moved into the trash
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagChange {
+    pub from: String,
+    // none when the tag is deleted
+    pub to: Option<String>,
+    // the tag is renamed to one that is already in use
+    pub merge: bool,
+    pub rewrites: Vec<TagRewrite>,
+    pub cutout: Option<CutoutChange>,
+}
+
+// the overview shows this image for the tag
+pub fn get_cutout_path(tag: &str) -> String {
+    format!("overview/{}_cutout.jpg", tag)
+}
+
+pub fn get_tags(config: &Config) -> Result<Vec<TagInfo>, ApiError> {
+    let tera = Tera::default();
+    let generator = Generator::new(&tera, config.get_input_path(), config.get_output_path(), None);
+
+    let mut tags: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (file, content) in read_posts(config)? {
+        let attributes = match generator.scan_attributes(&file, content.as_str()) {
+            Ok(attributes) => attributes,
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::UnprocessableEntity, e.message));
+            }
+        };
+        for (_, name, tag) in attributes {
+            if name != "tag" {
+                continue;
+            }
+            let posts = tags.entry(tag).or_insert(vec![]);
+            if !posts.contains(&file) {
+                posts.push(file.clone());
+            }
+        }
+    }
+
+    Ok(tags
+        .into_iter()
+        .map(|(name, posts)| {
+            let cutout = get_cutout_path(name.as_str());
+            TagInfo {
+                count: posts.len(),
+                posts,
+                cutout: if config.get_input_path().join(cutout.as_str()).is_file() { Some(cutout) } else { None },
+                name,
+            }
+        })
+        .collect())
+}
+
+// renames (or merges, if `to` is in use already) or deletes (`to` is none) the tag in all posts
+pub fn plan_tag_change(config: &Config, from: &str, to: Option<&str>) -> Result<TagChange, ApiError> {
+    if let Some(to) = to {
+        validate_tag(to)?;
+        if to == from {
+            return Err(ApiError::new(StatusCode::BadRequest, format!("tag is already named {}", to)));
+        }
+    }
+
+    let tera = Tera::default();
+    let generator = Generator::new(&tera, config.get_input_path(), config.get_output_path(), None);
+
+    let mut found = false;
+    let mut merge = false;
+    let mut rewrites = vec![];
+    for (file, content) in read_posts(config)? {
+        let attributes = match generator.scan_attributes(&file, content.as_str()) {
+            Ok(attributes) => attributes,
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::UnprocessableEntity, e.message));
+            }
+        };
+        let has_target = attributes.iter().any(|(_, name, value)| name == "tag" && Some(value.as_str()) == to);
+        merge = merge || has_target;
+
+        let matches: Vec<&Range<usize>> = attributes
+            .iter()
+            .filter(|(_, name, value)| name == "tag" && value == from)
+            .map(|(range, _, _)| range)
+            .collect();
+        if matches.is_empty() {
+            continue;
+        }
+        found = true;
+
+        let mut new_content = content.clone();
+        // replace from the back, so the ranges in front stay valid
+        for (idx, range) in matches.iter().enumerate().rev() {
+            match to {
+                // the first occurrence is renamed, duplicates are removed
+                Some(to) if !has_target && idx == 0 => {
+                    new_content.replace_range((*range).clone(), format!("[tag:{}]", to).as_str());
+                }
+                _ => {
+                    let end = if new_content[range.end..].starts_with('\n') { range.end + 1 } else { range.end };
+                    new_content.replace_range(range.start..end, "");
+                }
+            }
+        }
+
+        rewrites.push(TagRewrite {
+            diff: get_diff(file.as_str(), content.as_str(), new_content.as_str())?,
+            file,
+            content: new_content,
+        });
+    }
+    if !found {
+        return Err(ApiError::new(StatusCode::NotFound, format!("tag not found: {}", from)));
+    }
+
+    let cutout_from = get_cutout_path(from);
+    let cutout = if config.get_input_path().join(cutout_from.as_str()).is_file() {
+        // a merged tag keeps the image of the target, if it has one
+        let cutout_to = to.map(get_cutout_path).filter(|cutout_to| !config.get_input_path().join(cutout_to).exists());
+        Some(CutoutChange { from: cutout_from, to: cutout_to })
+    } else {
+        None
+    };
+
+    Ok(TagChange {
+        from: from.to_string(),
+        to: to.map(String::from),
+        merge,
+        rewrites,
+        cutout,
+    })
+}
+
+// writes the rewritten posts and moves the cutout image, returns the trashed image if any
+pub fn apply_tag_change(config: &Config, change: &TagChange, user: &str) -> Result<Option<TrashItem>, ApiError> {
+    for rewrite in change.rewrites.iter() {
+        if let Err(e) = fs::write(config.get_input_path().join(rewrite.file.as_str()), rewrite.content.as_bytes()) {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to write {}: {}", rewrite.file, e)));
+        }
+    }
+
+    match &change.cutout {
+        Some(CutoutChange { from, to: Some(to) }) => {
+            let input_path = config.get_input_path();
+            if let Err(e) = fs::rename(input_path.join(from.as_str()), input_path.join(to.as_str())) {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to rename {}: {}", from, e)));
+            }
+            Ok(None)
+        }
+        Some(CutoutChange { from, to: None }) => Ok(Some(move_to_trash(config, from.as_str(), user)?)),
+        None => Ok(None),
+    }
+}
+
+// tags end up in file names of the overview images
+fn validate_tag(tag: &str) -> Result<(), ApiError> {
+    if tag.is_empty() || !tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(ApiError::new(StatusCode::BadRequest, format!("invalid tag: {}", tag)));
+    }
+    Ok(())
+}
+
+fn read_posts(config: &Config) -> Result<Vec<(String, String)>, ApiError> {
+    let mut posts = vec![];
+    for file in find_files(&config.get_input_path(), Some(".md")) {
+        if file.is_dir {
+            continue;
+        }
+        match fs::read_to_string(config.get_input_path().join(file.name.as_str())) {
+            Ok(content) => posts.push((file.name, content)),
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read {}: {}", file.name, e)));
+            }
+        }
+    }
+    posts.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(posts)
+}
+
+fn get_diff(file: &str, old: &str, new: &str) -> Result<String, ApiError> {
+    let path = Path::new(file);
+    let to_error = |e: git2::Error| ApiError::new(StatusCode::InternalServerError, format!("unable to diff {}: {}", file, e.message()));
+    let mut patch = Patch::from_buffers(old.as_bytes(), Some(path), new.as_bytes(), Some(path), None).map_err(to_error)?;
+    let buf = patch.to_buf().map_err(to_error)?;
+    Ok(String::from_utf8_lossy(&buf).to_string())
+}
@@ -0,0 +1,193 @@
+use crate::blog::config::Config;
+use crate::blog::error::ApiError;
+use crate::blog::utils::join_relative;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tide::StatusCode;
+use walkdir::WalkDir;
+
+const META_FILE: &str = "meta.json";
+const ITEM_FILE: &str = "item";
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashItem {
+    pub id: String,
+    pub path: String,
+    pub time: u64,
+    pub user: String,
+    pub is_dir: bool,
+}
+
+// moves a path below the posts into the trash, every item gets its own folder with the metadata next to it
+pub fn move_to_trash(config: &Config, path: &str, user: &str) -> Result<TrashItem, ApiError> {
+    let source = match join_relative(&config.get_input_path(), path) {
+        Some(source) if source != config.get_input_path() => source,
+        _ => {
+            return Err(ApiError::new(StatusCode::BadRequest, format!("invalid path: {}", path)));
+        }
+    };
+    if !source.exists() {
+        return Err(ApiError::new(StatusCode::NotFound, format!("path not found: {}", path)));
+    }
+    let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("invalid system time: {}", e)));
+        }
+    };
+    let item = TrashItem {
+        id: format!("{}-{:09}", now.as_secs(), now.subsec_nanos()),
+        path: path.to_string(),
+        time: now.as_secs(),
+        user: user.to_string(),
+        is_dir: source.is_dir(),
+    };
+
+    let meta = match serde_json::to_string_pretty(&item) {
+        Ok(meta) => meta,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to serialize trash item: {}", e)));
+        }
+    };
+
+    // the metadata is written first, so a moved item can always be listed and restored
+    let item_path = config.get_trash_path().join(item.id.as_str());
+    if let Err(e) = fs::create_dir_all(&item_path).and_then(|_| fs::write(item_path.join(META_FILE), meta)) {
+        let _ = fs::remove_dir_all(&item_path);
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to write trash item: {}", e)));
+    }
+    if let Err(e) = move_path(&source, &item_path.join(ITEM_FILE)) {
+        let _ = fs::remove_dir_all(&item_path);
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to move to trash: {}", e)));
+    }
+
+    purge_trash(config)?;
+    Ok(item)
+}
+
+pub fn get_trash(config: &Config) -> Result<Vec<TrashItem>, ApiError> {
+    purge_trash(config)?;
+    read_trash(config)
+}
+
+pub fn restore_from_trash(config: &Config, id: &str) -> Result<TrashItem, ApiError> {
+    let item = match read_trash(config)?.into_iter().find(|item| item.id == id) {
+        Some(item) => item,
+        None => {
+            return Err(ApiError::new(StatusCode::NotFound, format!("trash item not found: {}", id)));
+        }
+    };
+
+    let target = config.get_input_path().join(item.path.as_str());
+    if target.exists() {
+        return Err(ApiError::new(StatusCode::Conflict, format!("path already exists: {}", item.path)));
+    }
+    if let Some(parent) = target.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to create parent folder: {}", e)));
+        }
+    }
+
+    let item_path = config.get_trash_path().join(item.id.as_str());
+    if let Err(e) = move_path(&item_path.join(ITEM_FILE), &target) {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to restore: {}", e)));
+    }
+    if let Err(e) = fs::remove_dir_all(&item_path) {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to remove trash item: {}", e)));
+    }
+
+    Ok(item)
+}
+
+// removes the given items, or all of them, returns the number of removed items
+pub fn empty_trash(config: &Config, ids: Option<Vec<String>>) -> Result<usize, ApiError> {
+    let mut removed = 0;
+    for item in read_trash(config)? {
+        if let Some(ids) = &ids {
+            if !ids.contains(&item.id) {
+                continue;
+            }
+        }
+        if let Err(e) = fs::remove_dir_all(config.get_trash_path().join(item.id.as_str())) {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to remove trash item: {}", e)));
+        }
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+// removes items older than the retention period
+pub fn purge_trash(config: &Config) -> Result<(), ApiError> {
+    if config.trash_retention_days == 0 {
+        return Ok(());
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let expired: Vec<String> = read_trash(config)?
+        .into_iter()
+        .filter(|item| item.time + config.trash_retention_days * SECONDS_PER_DAY < now)
+        .map(|item| item.id)
+        .collect();
+    if !expired.is_empty() {
+        empty_trash(config, Some(expired))?;
+    }
+    Ok(())
+}
+
+fn read_trash(config: &Config) -> Result<Vec<TrashItem>, ApiError> {
+    let trash_path = config.get_trash_path();
+    if !trash_path.exists() {
+        return Ok(vec![]);
+    }
+
+    let entries = match fs::read_dir(&trash_path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to list trash: {}", e)));
+        }
+    };
+    let mut items = vec![];
+    for entry in entries.filter_map(|e| e.ok()) {
+        // folders without metadata or item are left over from an interrupted move
+        if !entry.path().join(ITEM_FILE).exists() {
+            continue;
+        }
+        let meta = match fs::read_to_string(entry.path().join(META_FILE)) {
+            Ok(meta) => meta,
+            Err(_) => continue,
+        };
+        match serde_json::from_str::<TrashItem>(meta.as_str()) {
+            Ok(item) => items.push(item),
+            Err(e) => {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to parse trash item: {}", e)));
+            }
+        }
+    }
+    items.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(items)
+}
+
+// renames, or copies and removes if the data path is on another file system
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    if from.is_dir() {
+        for entry in WalkDir::new(from).into_iter().filter_map(|e| e.ok()) {
+            let target = to.join(entry.path().strip_prefix(from).unwrap());
+            if entry.file_type().is_dir() {
+                fs::create_dir_all(target)?;
+            } else {
+                fs::copy(entry.path(), target)?;
+            }
+        }
+        fs::remove_dir_all(from)
+    } else {
+        fs::copy(from, to)?;
+        fs::remove_file(from)
+    }
+}
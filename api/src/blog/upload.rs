@@ -0,0 +1,387 @@
+use crate::blog::config::Config;
+use crate::blog::error::ApiError;
+use crate::blog::utils::join_relative;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use rexiv2::Metadata;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tide::StatusCode;
+
+// enough bytes to detect all known types
+pub const SNIFF_SIZE: usize = 512;
+// binary stl files start with an 80 byte header and the triangle count
+const STL_HEADER_SIZE: usize = 84;
+
+// magic bytes at an offset for the upload types
+const MAGIC_BYTES: &[(&str, usize, &[u8])] = &[
+    ("jpg", 0, &[0xFF, 0xD8, 0xFF]),
+    ("png", 0, &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]),
+    ("gif", 0, b"GIF87a"),
+    ("gif", 0, b"GIF89a"),
+    ("webp", 8, b"WEBP"),
+    ("pdf", 0, b"%PDF-"),
+    ("mp4", 4, b"ftyp"),
+    ("webm", 0, &[0x1A, 0x45, 0xDF, 0xA3]),
+    ("zip", 0, &[b'P', b'K', 0x03, 0x04]),
+    ("gz", 0, &[0x1F, 0x8B]),
+    ("tar", 257, b"ustar"),
+];
+
+// file extensions of the same type
+const EXTENSION_ALIASES: &[(&str, &str)] = &[("jpeg", "jpg"), ("m4v", "mp4"), ("mov", "mp4"), ("tgz", "gz")];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadedFile {
+    pub name: String,
+    pub kind: String,
+    pub size: u64,
+}
+
+// detects the type from the first bytes, `extension` is only used for types without magic bytes
+pub fn sniff_type(header: &[u8], extension: &str) -> Option<&'static str> {
+    for (kind, offset, magic) in MAGIC_BYTES {
+        if header.len() >= offset + magic.len() && &header[*offset..offset + magic.len()] == *magic {
+            return Some(kind);
+        }
+    }
+    if extension == "stl" && (header.starts_with(b"solid") || header.len() >= STL_HEADER_SIZE) {
+        return Some("stl");
+    }
+    None
+}
+
+pub fn get_extension(name: &str) -> String {
+    let extension = match name.rsplit_once('.') {
+        Some((_, extension)) => extension.to_lowercase(),
+        None => String::new(),
+    };
+    match EXTENSION_ALIASES.iter().find(|(alias, _)| *alias == extension) {
+        Some((_, kind)) => kind.to_string(),
+        None => extension,
+    }
+}
+
+// returns the sniffed type of an upload and its max size, the type has to match the file extension
+// and `size` must not exceed the limit of the type
+pub fn check_type(limits: &HashMap<String, u64>, name: &str, header: &[u8], size: u64) -> Result<(&'static str, u64), ApiError> {
+    let extension = get_extension(name);
+    let kind = match sniff_type(header, extension.as_str()) {
+        Some(kind) => kind,
+        None => {
+            return Err(ApiError::new(StatusCode::UnsupportedMediaType, format!("unknown file type: {}", name)));
+        }
+    };
+    if kind != extension {
+        return Err(ApiError::new(
+            StatusCode::UnsupportedMediaType,
+            format!("content of {} is {}, not {}", name, kind, extension),
+        ));
+    }
+    let limit = match limits.get(kind) {
+        Some(limit) => *limit,
+        None => {
+            return Err(ApiError::new(StatusCode::UnsupportedMediaType, format!("file type not allowed: {}", kind)));
+        }
+    };
+    if size > limit {
+        return Err(too_large(name, limit, kind));
+    }
+    Ok((kind, limit))
+}
+
+fn too_large(name: &str, limit: u64, kind: &str) -> ApiError {
+    ApiError::new(
+        StatusCode::PayloadTooLarge,
+        format!("{} exceeds the limit of {} bytes for {}", name, limit, kind),
+    )
+}
+
+// returns the target path of an upload below the posts folder, which must not exist yet
+pub fn get_upload_path(config: &Config, name: &str) -> Result<PathBuf, ApiError> {
+    let path = match join_relative(&config.get_input_path(), name) {
+        Some(path) if path.file_name().is_some() => path,
+        _ => {
+            return Err(ApiError::new(StatusCode::BadRequest, format!("invalid file name: {}", name)));
+        }
+    };
+    if path.exists() {
+        return Err(ApiError::new(StatusCode::Conflict, format!("file already exists: {}", name)));
+    }
+    Ok(path)
+}
+
+// moves a completely received file into place after checking its type and size
+pub fn finish_file(config: &Config, name: &str, source: &PathBuf, process_images: bool) -> Result<UploadedFile, ApiError> {
+    let path = get_upload_path(config, name)?;
+    let to_error = |e: std::io::Error| ApiError::new(StatusCode::InternalServerError, format!("unable to read upload: {}", e));
+
+    let mut header = vec![0u8; SNIFF_SIZE];
+    let mut file = fs::File::open(source).map_err(to_error)?;
+    let header_size = file.read(&mut header).map_err(to_error)?;
+    header.truncate(header_size);
+    let mut size = file.metadata().map_err(to_error)?.len();
+    drop(file);
+
+    let (kind, _) = check_type(&config.upload_limits, name, &header, size)?;
+
+    if process_images && is_image(kind) {
+        process_image(config, source, kind)?;
+        size = fs::metadata(source).map_err(to_error)?.len();
+    }
+
+    if let Err(e) = fs::create_dir_all(path.parent().unwrap()) {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to create dir: {}", e)));
+    }
+    move_into_place(source, &path)?;
+
+    Ok(UploadedFile {
+        name: name.to_string(),
+        kind: kind.to_string(),
+        size,
+    })
+}
+
+// the source may be on another file system than the posts
+fn move_into_place(source: &PathBuf, path: &PathBuf) -> Result<(), ApiError> {
+    if fs::rename(source, path).is_err() {
+        if let Err(e) = fs::copy(source, path).and_then(|_| fs::remove_file(source)) {
+            let _ = fs::remove_file(path);
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to write: {}", e)));
+        }
+    }
+    Ok(())
+}
+
+// writes an upload into a temporary file below the data path,
+// which is moved into the posts only once the upload is complete and valid
+pub struct UploadWriter {
+    name: String,
+    temp_path: PathBuf,
+    file: Option<fs::File>,
+    header: Vec<u8>,
+    size: u64,
+    kind: Option<&'static str>,
+    limit: u64,
+}
+
+impl UploadWriter {
+    // only checks the name, the target folder is created when the upload is finished
+    pub fn create(config: &Config, name: &str) -> Result<UploadWriter, ApiError> {
+        get_upload_path(config, name)?;
+        let temp_dir = config.get_upload_temp_path();
+        if let Err(e) = fs::create_dir_all(&temp_dir) {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to create dir: {}", e)));
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        Ok(UploadWriter {
+            name: name.to_string(),
+            temp_path: temp_dir.join(format!("{}-{:09}.upload", now.as_secs(), now.subsec_nanos())),
+            file: None,
+            header: vec![],
+            size: 0,
+            kind: None,
+            limit: 0,
+        })
+    }
+
+    pub fn write(&mut self, config: &Config, chunk: &[u8]) -> Result<(), ApiError> {
+        self.size += chunk.len() as u64;
+        if let Some(kind) = self.kind {
+            if self.size > self.limit {
+                return Err(too_large(self.name.as_str(), self.limit, kind));
+            }
+        }
+
+        // collect the first bytes until the type can be detected
+        if self.kind.is_none() {
+            self.header.extend_from_slice(chunk);
+            if self.header.len() < SNIFF_SIZE {
+                return Ok(());
+            }
+            return self.detect_type(config);
+        }
+
+        self.write_to_file(chunk)
+    }
+
+    // writes the posts, so it has to be called with the repo lock held
+    pub fn finish(mut self, config: &Config) -> Result<UploadedFile, ApiError> {
+        if self.kind.is_none() {
+            self.detect_type(config)?;
+        }
+        if let Some(file) = self.file.take() {
+            if let Err(e) = file.sync_all() {
+                return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to write: {}", e)));
+            }
+        }
+        finish_file(config, self.name.as_str(), &self.temp_path, config.process_images)
+    }
+
+    fn detect_type(&mut self, config: &Config) -> Result<(), ApiError> {
+        let (kind, limit) = check_type(&config.upload_limits, self.name.as_str(), &self.header, self.size)?;
+        self.kind = Some(kind);
+        self.limit = limit;
+
+        let header = std::mem::take(&mut self.header);
+        self.write_to_file(&header)
+    }
+
+    fn write_to_file(&mut self, chunk: &[u8]) -> Result<(), ApiError> {
+        if self.file.is_none() {
+            self.file = match fs::File::create(&self.temp_path) {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to write: {}", e)));
+                }
+            };
+        }
+        if let Err(e) = self.file.as_mut().unwrap().write_all(chunk) {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to write: {}", e)));
+        }
+        Ok(())
+    }
+}
+
+// unfinished uploads leave nothing behind
+impl Drop for UploadWriter {
+    fn drop(&mut self) {
+        if self.temp_path.exists() {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+// types with metadata that may contain locations
+pub fn is_image(kind: &str) -> bool {
+    kind == "jpg" || kind == "png" || kind == "webp"
+}
+
+// applies the orientation, downscales and re-encodes jpg and png images, which drops all of their metadata,
+// the metadata (exif, iptc, xmp) of other images is stripped
+pub fn process_image(config: &Config, path: &PathBuf, kind: &str) -> Result<(), ApiError> {
+    if kind != "jpg" && kind != "png" {
+        return strip_metadata(path);
+    }
+
+    let to_error = |e: &dyn std::fmt::Display| ApiError::new(StatusCode::UnprocessableEntity, format!("unable to process image: {}", e));
+    let reader = ImageReader::open(path).map_err(|e| to_error(&e))?;
+    let mut decoder = reader
+        .with_guessed_format()
+        .map_err(|e| to_error(&e))?
+        .into_decoder()
+        .map_err(|e| to_error(&e))?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|e| to_error(&e))?;
+    image.apply_orientation(orientation);
+
+    let max_dimension = config.image_max_dimension;
+    if max_dimension > 0 && (image.width() > max_dimension || image.height() > max_dimension) {
+        image = image.resize(max_dimension, max_dimension, FilterType::Lanczos3);
+    }
+
+    let temp_path = path.with_extension("processing");
+    let file = match fs::File::create(&temp_path) {
+        Ok(file) => BufWriter::new(file),
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to write image: {}", e)));
+        }
+    };
+    let result = if kind == "jpg" {
+        // jpeg has no alpha channel
+        DynamicImage::ImageRgb8(image.to_rgb8()).write_with_encoder(JpegEncoder::new_with_quality(file, config.jpeg_quality))
+    } else {
+        image.write_with_encoder(PngEncoder::new(file))
+    };
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to encode image: {}", e)));
+    }
+    if let Err(e) = fs::rename(&temp_path, path) {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to write image: {}", e)));
+    }
+    Ok(())
+}
+
+fn strip_metadata(path: &PathBuf) -> Result<(), ApiError> {
+    let meta = match Metadata::new_from_path(path) {
+        Ok(meta) => meta,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::UnprocessableEntity, format!("unable to read image metadata: {}", e)));
+        }
+    };
+    meta.clear();
+    if let Err(e) = meta.save_to_file(path) {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to strip image metadata: {}", e)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(offset: usize, magic: &[u8]) -> Vec<u8> {
+        let mut header = vec![0u8; offset];
+        header.extend_from_slice(magic);
+        header.resize(SNIFF_SIZE, 0);
+        header
+    }
+
+    #[test]
+    fn sniffs_magic_bytes() {
+        for (kind, offset, magic) in MAGIC_BYTES {
+            assert_eq!(sniff_type(&header(*offset, magic), ""), Some(*kind));
+        }
+        assert_eq!(sniff_type(b"solid cube", "stl"), Some("stl"));
+        assert_eq!(sniff_type(&[0u8; STL_HEADER_SIZE], "stl"), Some("stl"));
+    }
+
+    #[test]
+    fn rejects_unknown_and_truncated_content() {
+        assert_eq!(sniff_type(b"<html><script>", "jpg"), None);
+        assert_eq!(sniff_type(&[0xFF, 0xD8], "jpg"), None);
+        assert_eq!(sniff_type(b"RIFF\0\0\0\0WEB", "webp"), None);
+        // the extension only matters for types without magic bytes
+        assert_eq!(sniff_type(b"solid cube", "txt"), None);
+        assert_eq!(sniff_type(&[0u8; STL_HEADER_SIZE - 1], "stl"), None);
+    }
+
+    #[test]
+    fn detects_content_not_matching_the_extension() {
+        let png = header(0, &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+        assert_eq!(sniff_type(&png, get_extension("photo.jpg").as_str()), Some("png"));
+        let zip = header(0, &[b'P', b'K', 0x03, 0x04]);
+        assert_eq!(sniff_type(&zip, get_extension("paper.pdf").as_str()), Some("zip"));
+    }
+
+    #[test]
+    fn checks_type_and_size_against_the_limits() {
+        let limits = HashMap::from([(String::from("png"), 100), (String::from("jpg"), 100)]);
+        let png = header(0, &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+        let pdf = header(0, b"%PDF-");
+        assert_eq!(check_type(&limits, "photo.png", &png, 100).unwrap(), ("png", 100));
+
+        let status = |name: &str, header: &[u8], size: u64| check_type(&limits, name, header, size).unwrap_err().status;
+        assert_eq!(status("photo.jpg", &png, 100), StatusCode::UnsupportedMediaType);
+        assert_eq!(status("paper.pdf", &pdf, 100), StatusCode::UnsupportedMediaType);
+        assert_eq!(status("notes.png", b"<html>", 100), StatusCode::UnsupportedMediaType);
+        assert_eq!(status("photo.png", &png, 101), StatusCode::PayloadTooLarge);
+    }
+
+    #[test]
+    fn maps_extension_aliases() {
+        assert_eq!(get_extension("Photo.JPEG"), "jpg");
+        assert_eq!(get_extension("clip.mov"), "mp4");
+        assert_eq!(get_extension("site.tar.tgz"), "gz");
+        assert_eq!(get_extension("README"), "");
+    }
+}
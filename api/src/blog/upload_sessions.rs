@@ -0,0 +1,214 @@
+use crate::blog::config::Config;
+use crate::blog::error::ApiError;
+use crate::blog::upload::{finish_file, get_extension, get_upload_path, UploadedFile};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tide::StatusCode;
+
+const META_FILE: &str = "meta.json";
+const DATA_FILE: &str = "data";
+// abandoned uploads are removed after a week
+const SESSION_EXPIRY_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+// a resumable upload, the received data is kept in the data path until it is finalized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadSession {
+    pub id: String,
+    pub name: String,
+    pub size: u64,
+    pub created: u64,
+    #[serde(default)]
+    pub offset: u64,
+}
+
+pub fn create_session(config: &Config, name: &str, size: u64) -> Result<UploadSession, ApiError> {
+    purge_sessions(config)?;
+
+    get_upload_path(config, name)?;
+    // the content can only be checked on finalize, so reject what is too large already by the extension
+    let extension = get_extension(name);
+    match config.upload_limits.get(extension.as_str()) {
+        Some(limit) if size > *limit => {
+            return Err(ApiError::new(
+                StatusCode::PayloadTooLarge,
+                format!("{} exceeds the limit of {} bytes for {}", name, limit, extension),
+            ));
+        }
+        Some(_) => {}
+        None => {
+            return Err(ApiError::new(StatusCode::UnsupportedMediaType, format!("file type not allowed: {}", extension)));
+        }
+    }
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let session = UploadSession {
+        id: format!("{}-{:09}", now.as_secs(), now.subsec_nanos()),
+        name: name.to_string(),
+        size,
+        created: now.as_secs(),
+        offset: 0,
+    };
+
+    let session_path = get_session_path(config, session.id.as_str());
+    let meta = match serde_json::to_string_pretty(&session) {
+        Ok(meta) => meta,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to serialize upload: {}", e)));
+        }
+    };
+    if let Err(e) = fs::create_dir_all(&session_path)
+        .and_then(|_| fs::File::create(session_path.join(DATA_FILE)))
+        .and_then(|_| fs::write(session_path.join(META_FILE), meta))
+    {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to create upload: {}", e)));
+    }
+
+    Ok(session)
+}
+
+// the offset is the amount of data received so far
+pub fn get_session(config: &Config, id: &str) -> Result<UploadSession, ApiError> {
+    let session_path = get_session_path(config, id);
+    let meta = match fs::read_to_string(session_path.join(META_FILE)) {
+        Ok(meta) => meta,
+        Err(_) => {
+            return Err(ApiError::new(StatusCode::NotFound, format!("upload not found: {}", id)));
+        }
+    };
+    let mut session: UploadSession = match serde_json::from_str(meta.as_str()) {
+        Ok(session) => session,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to parse upload: {}", e)));
+        }
+    };
+    session.offset = match fs::metadata(session_path.join(DATA_FILE)) {
+        Ok(metadata) => metadata.len(),
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read upload: {}", e)));
+        }
+    };
+    Ok(session)
+}
+
+// opens the data file for a chunk starting at offset, which has to continue the received data
+pub fn open_chunk(config: &Config, id: &str, offset: u64) -> Result<(UploadSession, fs::File), ApiError> {
+    let session = get_session(config, id)?;
+    if offset != session.offset {
+        return Err(ApiError::new(
+            StatusCode::Conflict,
+            format!("chunk offset {} does not match the received size {}", offset, session.offset),
+        ));
+    }
+
+    let mut file = match fs::OpenOptions::new().write(true).open(get_session_path(config, id).join(DATA_FILE)) {
+        Ok(file) => file,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to open upload: {}", e)));
+        }
+    };
+    if let Err(e) = file.seek(SeekFrom::Start(offset)) {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to open upload: {}", e)));
+    }
+    Ok((session, file))
+}
+
+// appends a part of a chunk, a chunk must not exceed the announced size
+pub fn write_chunk(session: &mut UploadSession, file: &mut fs::File, chunk: &[u8]) -> Result<(), ApiError> {
+    if session.offset + chunk.len() as u64 > session.size {
+        return Err(ApiError::new(
+            StatusCode::PayloadTooLarge,
+            format!("chunk exceeds the announced size of {} bytes", session.size),
+        ));
+    }
+    if let Err(e) = file.write_all(chunk) {
+        return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to write upload: {}", e)));
+    }
+    session.offset += chunk.len() as u64;
+    Ok(())
+}
+
+// moves the upload into place once it is complete and matches the sha256 checksum
+pub fn finalize_session(config: &Config, id: &str, checksum: &str) -> Result<UploadedFile, ApiError> {
+    let session = get_session(config, id)?;
+    if session.offset != session.size {
+        return Err(ApiError::new(
+            StatusCode::Conflict,
+            format!("upload is incomplete, received {} of {} bytes", session.offset, session.size),
+        ));
+    }
+
+    let session_path = get_session_path(config, id);
+    let data_path = session_path.join(DATA_FILE);
+    let actual_checksum = match sha256_file(&data_path) {
+        Ok(checksum) => checksum,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to read upload: {}", e)));
+        }
+    };
+    if actual_checksum != checksum.to_lowercase() {
+        return Err(ApiError::new(
+            StatusCode::UnprocessableEntity,
+            format!("checksum mismatch, received data has {}", actual_checksum),
+        ));
+    }
+
+    let uploaded_file = finish_file(config, session.name.as_str(), &data_path, config.process_images)?;
+    let _ = fs::remove_dir_all(session_path);
+    Ok(uploaded_file)
+}
+
+pub fn delete_session(config: &Config, id: &str) -> Result<(), ApiError> {
+    get_session(config, id)?;
+    match fs::remove_dir_all(get_session_path(config, id)) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(ApiError::new(StatusCode::InternalServerError, format!("unable to remove upload: {}", e))),
+    }
+}
+
+fn purge_sessions(config: &Config) -> Result<(), ApiError> {
+    let sessions_path = config.get_upload_sessions_path();
+    if !sessions_path.exists() {
+        return Ok(());
+    }
+    let entries = match fs::read_dir(&sessions_path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            return Err(ApiError::new(StatusCode::InternalServerError, format!("unable to list uploads: {}", e)));
+        }
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    for entry in entries.filter_map(|e| e.ok()) {
+        let id = entry.file_name().to_string_lossy().to_string();
+        match get_session(config, id.as_str()) {
+            Ok(session) if session.created + SESSION_EXPIRY_SECONDS >= now => {}
+            _ => {
+                let _ = fs::remove_dir_all(entry.path());
+            }
+        }
+    }
+    Ok(())
+}
+
+// ids are generated by us, anything else can't name a session
+fn get_session_path(config: &Config, id: &str) -> PathBuf {
+    let id: String = id.chars().filter(|c| c.is_ascii_digit() || *c == '-').collect();
+    config.get_upload_sessions_path().join(id)
+}
+
+fn sha256_file(path: &PathBuf) -> std::io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
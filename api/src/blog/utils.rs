@@ -1,11 +1,19 @@
-use crate::blog::config::DEFAULT_BRANCH;
-use git2::{Delta, DiffDelta, DiffOptions, Index, Patch, Repository};
+use crate::blog::config::{Config, DEFAULT_BRANCH, REF_NAME, REMOTE_NAME, TRACKING_REF_NAME};
+use git2::{
+    Commit, Cred, Delta, DiffDelta, DiffOptions, FetchOptions, Index, IndexEntry, IndexTime, MergeFileOptions,
+    ObjectType, Oid, Patch, RemoteCallbacks, Repository,
+};
 use regex::Regex;
+use std::collections::{HashMap, HashSet};
 use serde::Serialize;
+use std::fs;
 use std::path::Path;
+use std::time::{Duration, SystemTime};
 use std::{borrow::BorrowMut, path::PathBuf};
 use walkdir::WalkDir;
 
+const MERGE_BASE_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
 #[derive(Debug, Serialize)]
 pub struct File {
     pub name: String,
@@ -45,6 +53,25 @@ pub struct Diff {
     pub content: String,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitSummary {
+    pub id: String,
+    pub summary: String,
+    pub author: String,
+    pub time: i64,
+}
+
+impl CommitSummary {
+    pub fn from_commit(commit: &Commit) -> CommitSummary {
+        CommitSummary {
+            id: commit.id().to_string(),
+            summary: String::from(commit.summary().unwrap_or("")),
+            author: String::from(commit.author().name().unwrap_or("")),
+            time: commit.time().seconds(),
+        }
+    }
+}
+
 pub fn find_files(path: &PathBuf, filter: Option<&str>) -> Vec<File> {
     let mut files: Vec<File> = Vec::new();
     for file in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
@@ -53,7 +80,7 @@ pub fn find_files(path: &PathBuf, filter: Option<&str>) -> Vec<File> {
             .to_string_lossy()
             .replace(format!("{}/", path.to_string_lossy()).as_str(), "");
         // skip empty names and same as path names
-        if name.len() < 1 || name == path.to_string_lossy() {
+        if name.is_empty() || name == path.to_string_lossy() {
             continue;
         }
         // skip hidden directories (e.g. .git)
@@ -69,7 +96,7 @@ pub fn find_files(path: &PathBuf, filter: Option<&str>) -> Vec<File> {
             is_dir: file.path().is_dir(),
         });
     }
-    return files;
+    files
 }
 
 pub fn get_entries(files: &mut Vec<File>) -> (Vec<Entry>, Vec<UnknownEntry>) {
@@ -83,7 +110,7 @@ pub fn get_entries(files: &mut Vec<File>) -> (Vec<Entry>, Vec<UnknownEntry>) {
                 assets: vec![],
             });
         }
-        return !is_markdown;
+        !is_markdown
     });
 
     files.retain(|file| {
@@ -95,11 +122,11 @@ pub fn get_entries(files: &mut Vec<File>) -> (Vec<Entry>, Vec<UnknownEntry>) {
                     return Some(idx);
                 }
             }
-            return None;
+            None
         };
         let entry_index = find_entry_index(file.name.clone(), &entries);
-        if entry_index.is_some() {
-            let entry: &mut Entry = entries.get_mut(entry_index.unwrap()).unwrap();
+        if let Some(entry_index) = entry_index {
+            let entry: &mut Entry = entries.get_mut(entry_index).unwrap();
             if !file.is_dir {
                 entry.assets.push(file.name.clone());
             }
@@ -109,10 +136,10 @@ pub fn get_entries(files: &mut Vec<File>) -> (Vec<Entry>, Vec<UnknownEntry>) {
                 is_dir: file.is_dir,
             });
         }
-        return !entry_index.is_some();
+        entry_index.is_none()
     });
 
-    return (entries, unknown_entries);
+    (entries, unknown_entries)
 }
 
 pub fn get_changes(repo: &Repository) -> Vec<Change> {
@@ -133,18 +160,16 @@ pub fn get_changes(repo: &Repository) -> Vec<Change> {
     for status in statuses.iter() {
 
         // add staged files
-        let mut diff_delta = status.head_to_index();
-        if diff_delta.is_some() {
-            add_change(&mut changes, &index, status.path().unwrap(), &diff_delta.unwrap(), true);
+        if let Some(diff_delta) = status.head_to_index() {
+            add_change(&mut changes, &index, status.path().unwrap(), &diff_delta, true);
         }
 
         // add unstaged files
-        diff_delta = status.index_to_workdir();
-        if diff_delta.is_some() {
-            add_change(&mut changes, &index, status.path().unwrap(), &diff_delta.unwrap(), false);
+        if let Some(diff_delta) = status.index_to_workdir() {
+            add_change(&mut changes, &index, status.path().unwrap(), &diff_delta, false);
         }
     }
-    return changes;
+    changes
 }
 
 fn add_change(changes: &mut Vec<Change>, index: &Index, path: &str, diff_delta: &DiffDelta, staged: bool) {
@@ -175,7 +200,7 @@ pub fn get_diffs(repo: &Repository) -> Vec<Diff> {
     add_diff(&mut diffs, &repo
         .diff_tree_to_workdir_with_index(Some(&reference.peel_to_commit().unwrap().tree().unwrap()), Some(&mut diff_options)).unwrap());
 
-    return diffs;
+    diffs
 }
 
 fn add_diff(diffs: &mut Vec<Diff>, diff: &git2::Diff) {
@@ -184,14 +209,14 @@ fn add_diff(diffs: &mut Vec<Diff>, diff: &git2::Diff) {
         return;
     }
 
+    let diff_name_pattern = Regex::new(r#"diff --git a/(.+?) b/(.+?)\n"#).unwrap_or_else(|e| {
+        panic!("error: {}", e);
+    });
     for idx in 0..stats.files_changed() - 1 {
-        let patch = Patch::from_diff(&diff, idx);
+        let patch = Patch::from_diff(diff, idx);
         let patch_content = String::from(patch.unwrap().unwrap().to_buf().unwrap().as_str().unwrap());
-        let diff_name_pattern = Regex::new(r#"diff --git a/(.+?) b/(.+?)\n"#).unwrap_or_else(|e| {
-            panic!("error: {}", e);
-        });
         for cap in diff_name_pattern.captures_iter(patch_content.as_str()) {
-            if &cap[1] != &cap[2] {
+            if cap[1] != cap[2] {
                 panic!("patch name mismatch: {} != {}", &cap[1], &cap[2]);
             }
             diffs.push(Diff {
@@ -200,4 +225,285 @@ fn add_diff(diffs: &mut Vec<Diff>, diff: &git2::Diff) {
             });
         }
     }
-}
\ No newline at end of file
+}
+
+pub fn get_remote_callbacks(config: &Config) -> RemoteCallbacks<'_> {
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |_url, username_from_url, _allowed_types| {
+        Cred::ssh_key(
+            username_from_url.unwrap(),
+            None,
+            Path::new(&config.git_ssh_key_path),
+            None,
+        )
+    });
+    callbacks
+}
+
+// fetches the default branch of the remote into the remote tracking branch
+pub fn fetch_remote(config: &Config, repo: &Repository) -> Result<(), git2::Error> {
+    let mut remote = repo.find_remote(REMOTE_NAME)?;
+    let mut fetch_option = FetchOptions::new();
+    fetch_option.remote_callbacks(get_remote_callbacks(config));
+    remote.fetch(&[format!("+{}:{}", REF_NAME, TRACKING_REF_NAME)], Some(&mut fetch_option), None)
+}
+
+// list commits reachable from `from` but not from `hide`, newest first
+pub fn get_commits_between(repo: &Repository, from: Oid, hide: Oid) -> Result<Vec<CommitSummary>, git2::Error> {
+    let mut commits = vec![];
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(from)?;
+    revwalk.hide(hide)?;
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        commits.push(CommitSummary::from_commit(&commit));
+    }
+    Ok(commits)
+}
+
+// (file name, content) of the markdown sources and all file names
+pub type StagedFiles = (Vec<(String, String)>, Vec<String>);
+
+// returns the markdown sources and all file names as staged in the index
+pub fn get_staged_files(repo: &Repository) -> Result<StagedFiles, git2::Error> {
+    let mut sources = vec![];
+    let mut names = vec![];
+    let index = repo.index()?;
+    for entry in index.iter() {
+        let name = String::from_utf8_lossy(&entry.path).to_string();
+        if name.ends_with(".md") && !name.starts_with(".") {
+            let blob = repo.find_blob(entry.id)?;
+            match std::str::from_utf8(blob.content()) {
+                Ok(content) => sources.push((name.clone(), content.to_string())),
+                Err(e) => {
+                    return Err(git2::Error::from_str(format!("{} is not valid utf-8: {}", name, e).as_str()));
+                }
+            }
+        }
+        names.push(name);
+    }
+    Ok((sources, names))
+}
+
+// returns all markdown files of the HEAD commit as (name, content)
+pub fn get_head_files(repo: &Repository) -> Result<Vec<(String, String)>, git2::Error> {
+    let mut sources = vec![];
+    let tree = match repo.head() {
+        Ok(head) => head.peel_to_tree()?,
+        Err(_) => return Ok(sources),
+    };
+    let mut error: Option<git2::Error> = None;
+    tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+        let name = format!("{}{}", root, entry.name().unwrap_or(""));
+        if entry.kind() != Some(ObjectType::Blob) || !name.ends_with(".md") || name.starts_with(".") {
+            return git2::TreeWalkResult::Ok;
+        }
+        match repo.find_blob(entry.id()) {
+            Ok(blob) => match std::str::from_utf8(blob.content()) {
+                Ok(content) => sources.push((name, content.to_string())),
+                Err(e) => {
+                    error = Some(git2::Error::from_str(format!("{} is not valid utf-8: {}", name, e).as_str()));
+                    return git2::TreeWalkResult::Abort;
+                }
+            },
+            Err(e) => {
+                error = Some(e);
+                return git2::TreeWalkResult::Abort;
+            }
+        }
+        git2::TreeWalkResult::Ok
+    })?;
+    match error {
+        Some(e) => Err(e),
+        None => Ok(sources),
+    }
+}
+
+// checks whether the commit is contained in the remote branch as of the last fetch or push,
+// none if the remote state is unknown (never fetched or pushed)
+pub fn is_on_remote(repo: &Repository, oid: Oid) -> Option<bool> {
+    if repo.find_remote(REMOTE_NAME).is_err() {
+        return Some(false);
+    }
+    let remote_oid = repo.refname_to_id(TRACKING_REF_NAME).ok()?;
+    if remote_oid == oid {
+        return Some(true);
+    }
+    repo.graph_descendant_of(remote_oid, oid).ok()
+}
+
+// lists all paths that differ between the tree of `reference` and the tree of commit `oid`
+pub fn get_changed_paths(repo: &Repository, reference: &str, oid: Oid) -> Result<Vec<String>, git2::Error> {
+    let old_tree = repo.find_reference(reference)?.peel_to_commit()?.tree()?;
+    let new_tree = repo.find_commit(oid)?.tree()?;
+    let diff = repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), None)?;
+
+    let mut paths = vec![];
+    for diff_delta in diff.deltas() {
+        for file in [diff_delta.old_file(), diff_delta.new_file()].iter() {
+            if let Some(path) = file.path() {
+                let path = path.to_string_lossy().to_string();
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+    }
+    Ok(paths)
+}
+
+// git blob id of the content, used as ETag for files
+pub fn content_hash(content: &[u8]) -> String {
+    match Oid::hash_object(ObjectType::Blob, content) {
+        Ok(oid) => oid.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+pub fn new_index_entry(path: &str, id: Oid, file_size: u32) -> IndexEntry {
+    IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode: 0o100644,
+        uid: 0,
+        gid: 0,
+        file_size,
+        id,
+        flags: 0,
+        flags_extended: 0,
+        path: path.as_bytes().to_vec(),
+    }
+}
+
+// keeps a copy of content handed out to a client outside of the repository,
+// so it can serve as merge base if saving conflicts later, returns its hash
+pub fn store_merge_base(config: &Config, content: &[u8]) -> std::io::Result<String> {
+    let hash = content_hash(content);
+    let bases_path = config.get_merge_bases_path();
+    fs::create_dir_all(&bases_path)?;
+
+    // forget the bases nobody read for a while
+    for entry in fs::read_dir(&bases_path)?.filter_map(|e| e.ok()) {
+        let is_expired = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .map(|modified| modified.elapsed().unwrap_or_default() > MERGE_BASE_EXPIRY)
+            .unwrap_or(true);
+        if is_expired {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+
+    let path = bases_path.join(hash.as_str());
+    if path.exists() {
+        // refresh, so it doesn't expire while in use
+        let file = fs::File::options().append(true).open(&path)?;
+        file.set_modified(SystemTime::now())?;
+    } else {
+        let temp_path = bases_path.join(format!(".{}", hash));
+        fs::write(&temp_path, content)?;
+        fs::rename(&temp_path, &path)?;
+    }
+    Ok(hash)
+}
+
+// the base as stored when it was read, or as known to git (e.g. committed or staged)
+pub fn load_merge_base(config: &Config, repo: &Repository, hash: &str) -> Option<Vec<u8>> {
+    let oid = Oid::from_str(hash).ok()?;
+    match fs::read(config.get_merge_bases_path().join(oid.to_string())) {
+        Ok(content) => Some(content),
+        Err(_) => repo.find_blob(oid).ok().map(|blob| blob.content().to_vec()),
+    }
+}
+
+// three-way merges two versions of a file based on `base`, this is the only place writing blobs for it,
+// returns whether the merge is clean and the merged content (with conflict markers otherwise)
+pub fn merge_contents(
+    repo: &Repository,
+    path: &str,
+    base: &[u8],
+    ours: &[u8],
+    theirs: &[u8],
+) -> Result<(bool, String), git2::Error> {
+    let base_id = repo.blob(base)?;
+    let ours_id = repo.blob(ours)?;
+    let theirs_id = repo.blob(theirs)?;
+
+    let mut options = MergeFileOptions::new();
+    options.ancestor_label("base").our_label("saved").their_label("yours");
+    let result = repo.merge_file_from_index(
+        &new_index_entry(path, base_id, base.len() as u32),
+        &new_index_entry(path, ours_id, ours.len() as u32),
+        &new_index_entry(path, theirs_id, theirs.len() as u32),
+        Some(&mut options),
+    )?;
+    Ok((result.is_automergeable(), String::from_utf8_lossy(result.content()).to_string()))
+}
+
+// joins a relative path from a request onto base, refusing anything that could leave base
+pub fn join_relative(base: &Path, path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    if path.as_os_str().is_empty() {
+        return None;
+    }
+    for component in path.components() {
+        match component {
+            std::path::Component::Normal(_) | std::path::Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(base.join(path))
+}
+
+// returns the newest commit changing each of the paths by walking the history once
+pub fn get_last_commits(repo: &Repository, paths: &HashSet<String>) -> Result<HashMap<String, CommitSummary>, git2::Error> {
+    let mut last_commits = HashMap::new();
+    if repo.head().is_err() {
+        return Ok(last_commits);
+    }
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+    revwalk.push_head()?;
+    for oid in revwalk {
+        if last_commits.len() == paths.len() {
+            break;
+        }
+        let commit = repo.find_commit(oid?)?;
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        for delta in diff.deltas() {
+            if let Some(path) = delta.new_file().path().map(|p| p.to_string_lossy().to_string()) {
+                if paths.contains(&path) && !last_commits.contains_key(&path) {
+                    last_commits.insert(path, CommitSummary::from_commit(&commit));
+                }
+            }
+        }
+    }
+    Ok(last_commits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_relative_paths() {
+        let base = PathBuf::from("/blog/posts");
+        assert_eq!(join_relative(&base, "post.md"), Some(PathBuf::from("/blog/posts/post.md")));
+        assert_eq!(join_relative(&base, "./post/image.jpg"), Some(PathBuf::from("/blog/posts/post/image.jpg")));
+    }
+
+    #[test]
+    fn rejects_paths_leaving_the_base() {
+        let base = PathBuf::from("/blog/posts");
+        for path in ["", "..", "../secret", "post/../../secret", "/etc/passwd"] {
+            assert_eq!(join_relative(&base, path), None, "{}", path);
+        }
+    }
+}
@@ -4,28 +4,69 @@ use clap::Command;
 use std::env;
 use std::path::Path;
 use std::process;
-use tera::Tera;
 
 use crate::blog::auth_middleware::AuthMiddleware;
 use tide_rustls::TlsListener;
 
 use crate::blog::config::Config;
+use crate::blog::ctrl_autosave::ctrl_autosave;
+use crate::blog::ctrl_change_post_status::ctrl_change_post_status;
 use crate::blog::ctrl_commit::ctrl_commit;
 use crate::blog::ctrl_delete::ctrl_delete;
+use crate::blog::ctrl_delete_assets::ctrl_delete_assets;
+use crate::blog::ctrl_delete_post::ctrl_delete_post;
+use crate::blog::ctrl_delete_tag::ctrl_delete_tag;
+use crate::blog::ctrl_delete_upload_session::ctrl_delete_upload_session;
+use crate::blog::ctrl_empty_trash::ctrl_empty_trash;
+use crate::blog::ctrl_finalize_upload_session::ctrl_finalize_upload_session;
+use crate::blog::ctrl_discard_autosave::ctrl_discard_autosave;
 use crate::blog::ctrl_generate::ctrl_generate;
+use crate::blog::ctrl_get_assets::ctrl_get_assets;
+use crate::blog::ctrl_get_autosave::ctrl_get_autosave;
+use crate::blog::ctrl_get_autosaves::ctrl_get_autosaves;
 use crate::blog::ctrl_get_changes::ctrl_get_changes;
+use crate::blog::ctrl_get_deployments::ctrl_get_deployments;
+use crate::blog::ctrl_get_file::ctrl_get_file;
 use crate::blog::ctrl_get_files::ctrl_get_files;
+use crate::blog::ctrl_get_job::ctrl_get_job;
+use crate::blog::ctrl_get_job_events::ctrl_get_job_events;
+use crate::blog::ctrl_get_jobs::ctrl_get_jobs;
+use crate::blog::ctrl_get_posts::ctrl_get_posts;
 use crate::blog::ctrl_get_preview::ctrl_get_preview;
+use crate::blog::ctrl_get_redirects::ctrl_get_redirects;
+use crate::blog::ctrl_get_stashes::ctrl_get_stashes;
+use crate::blog::ctrl_get_status_history::ctrl_get_status_history;
+use crate::blog::ctrl_get_statuses::ctrl_get_statuses;
+use crate::blog::ctrl_get_sync_status::ctrl_get_sync_status;
+use crate::blog::ctrl_get_tags::ctrl_get_tags;
+use crate::blog::ctrl_get_trash::ctrl_get_trash;
+use crate::blog::ctrl_get_upload_session::ctrl_get_upload_session;
+use crate::blog::ctrl_move_post::ctrl_move_post;
+use crate::blog::ctrl_hook_push::ctrl_hook_push;
 use crate::blog::ctrl_new_file::ctrl_new_file;
 use crate::blog::ctrl_new_folder::ctrl_new_folder;
+use crate::blog::ctrl_new_job::ctrl_new_job;
+use crate::blog::ctrl_new_upload_session::ctrl_new_upload_session;
+use crate::blog::ctrl_publish::ctrl_publish;
+use crate::blog::ctrl_put_upload_chunk::ctrl_put_upload_chunk;
 use crate::blog::ctrl_pull_remote::ctrl_pull_remote;
 use crate::blog::ctrl_push_remote::ctrl_push_remote;
 use crate::blog::ctrl_rename::ctrl_rename;
+use crate::blog::ctrl_rename_tag::ctrl_rename_tag;
+use crate::blog::ctrl_restore_trash::ctrl_restore_trash;
 use crate::blog::ctrl_revert::ctrl_revert;
+use crate::blog::ctrl_rollback_deployment::ctrl_rollback_deployment;
 use crate::blog::ctrl_save::ctrl_save;
+use crate::blog::ctrl_search::ctrl_search;
 use crate::blog::ctrl_stage::ctrl_stage;
+use crate::blog::ctrl_stash_apply::ctrl_stash_apply;
+use crate::blog::ctrl_stash_drop::ctrl_stash_drop;
+use crate::blog::ctrl_stash_push::ctrl_stash_push;
 use crate::blog::ctrl_upload::ctrl_upload;
-use crate::blog::generator::generate_all;
+use crate::blog::ctrl_upload_archive::ctrl_upload_archive;
+use crate::blog::generator::{generate_all, new_tera};
+use crate::blog::publisher::publish;
+use crate::blog::trash::purge_trash;
 
 #[async_std::main]
 async fn main() {
@@ -41,7 +82,7 @@ async fn main() {
         )
     }
 
-    let tera = match Tera::new(format!("{}/templates/*.html", config.working_path).as_str()) {
+    let tera = match new_tera(&config) {
         Ok(t) => t,
         Err(e) => panic!("Unable to generate config: {}", e),
     };
@@ -49,17 +90,26 @@ async fn main() {
     let matches = Command::new("ohmyblog")
         .subcommand_required(true)
         .subcommand(Command::new("generate").about("generate all or specific files"))
+        .subcommand(Command::new("publish").about("publish generated files to the deploy targets"))
         .subcommand(Command::new("webserver").about("starts the webserver"))
         .get_matches();
 
-    if let Some(_) = matches.subcommand_matches("generate") {
+    if matches.subcommand_matches("generate").is_some() {
         if let Err(e) = generate_all(&config, &tera) {
             panic!("Unable to generate file: {:?}", e.message)
         }
         return;
     }
 
-    if let Some(_) = matches.subcommand_matches("webserver") {
+    if matches.subcommand_matches("publish").is_some() {
+        match publish(&config, false) {
+            Ok(deployment) => println!("published deployment {}", deployment.id),
+            Err(e) => panic!("Unable to publish: {}", e.message),
+        }
+        return;
+    }
+
+    if matches.subcommand_matches("webserver").is_some() {
         webserver(config).await;
     }
 }
@@ -73,6 +123,9 @@ async fn webserver(config: Config) {
         );
         process::exit(1);
     }
+    if let Err(e) = purge_trash(&config) {
+        eprintln!("unable to purge trash: {}", e.message);
+    }
 
     let mut app = tide::with_state(config);
     if let Err(e) = app.at("/").serve_dir(working_path) {
@@ -80,26 +133,66 @@ async fn webserver(config: Config) {
     }
     app.with(AuthMiddleware {});
     app.at("/api/files").get(ctrl_get_files);
+    app.at("/api/file").get(ctrl_get_file);
+    app.at("/api/posts").get(ctrl_get_posts);
+    app.at("/api/assets").get(ctrl_get_assets);
+    app.at("/api/assets/delete").post(ctrl_delete_assets);
     app.at("/api/changes").get(ctrl_get_changes);
+    app.at("/api/search").get(ctrl_search);
+    app.at("/api/tags").get(ctrl_get_tags);
+    app.at("/api/tags/rename").post(ctrl_rename_tag);
+    app.at("/api/tags/delete").post(ctrl_delete_tag);
     app.at("/api/preview").post(ctrl_get_preview);
     app.at("/api/file/new").post(ctrl_new_file);
     app.at("/api/folder/new").post(ctrl_new_folder);
     app.at("/api/stage").post(ctrl_stage);
     app.at("/api/revert").post(ctrl_revert);
     app.at("/api/upload").post(ctrl_upload);
+    app.at("/api/upload/archive").post(ctrl_upload_archive);
+    app.at("/api/uploads").post(ctrl_new_upload_session);
+    app.at("/api/uploads/:id").get(ctrl_get_upload_session);
+    app.at("/api/uploads/:id").put(ctrl_put_upload_chunk);
+    app.at("/api/uploads/:id").delete(ctrl_delete_upload_session);
+    app.at("/api/uploads/:id/finalize").post(ctrl_finalize_upload_session);
     app.at("/api/save").post(ctrl_save);
+    app.at("/api/autosaves").get(ctrl_get_autosaves);
+    app.at("/api/autosave").get(ctrl_get_autosave);
+    app.at("/api/autosave").post(ctrl_autosave);
+    app.at("/api/autosave/discard").post(ctrl_discard_autosave);
     app.at("/api/rename").post(ctrl_rename);
     app.at("/api/delete").post(ctrl_delete);
+    app.at("/api/post/move").post(ctrl_move_post);
+    app.at("/api/post/status").post(ctrl_change_post_status);
+    app.at("/api/post/status/history").get(ctrl_get_status_history);
+    app.at("/api/statuses").get(ctrl_get_statuses);
+    app.at("/api/post/delete").post(ctrl_delete_post);
+    app.at("/api/trash").get(ctrl_get_trash);
+    app.at("/api/trash/restore").post(ctrl_restore_trash);
+    app.at("/api/trash/empty").post(ctrl_empty_trash);
     app.at("/api/commit").post(ctrl_commit);
     app.at("/api/generate").post(ctrl_generate);
     app.at("/api/push_remote").post(ctrl_push_remote);
     app.at("/api/pull_remote").post(ctrl_pull_remote);
+    app.at("/api/sync_status").get(ctrl_get_sync_status);
+    app.at("/api/stash").get(ctrl_get_stashes);
+    app.at("/api/stash/push").post(ctrl_stash_push);
+    app.at("/api/stash/apply").post(ctrl_stash_apply);
+    app.at("/api/stash/drop").post(ctrl_stash_drop);
+    app.at("/api/publish").post(ctrl_publish);
+    app.at("/api/redirects").get(ctrl_get_redirects);
+    app.at("/api/deployments").get(ctrl_get_deployments);
+    app.at("/api/deployments/rollback").post(ctrl_rollback_deployment);
+    app.at("/api/jobs").get(ctrl_get_jobs);
+    app.at("/api/jobs").post(ctrl_new_job);
+    app.at("/api/jobs/:id").get(ctrl_get_job);
+    app.at("/api/jobs/:id/events").get(tide::sse::endpoint(ctrl_get_job_events));
+    app.at("/hooks/push").post(ctrl_hook_push);
 
     let listen = env::var("LISTEN").unwrap_or(String::from("127.0.0.1:8080"));
     let tide_cert_path = env::var("TIDE_CERT_PATH").unwrap_or(String::from(""));
     let tide_key_path = env::var("TIDE_KEY_PATH").unwrap_or(String::from(""));
 
-    if tide_cert_path.len() > 0 && tide_key_path.len() > 0 {
+    if !tide_cert_path.is_empty() && !tide_key_path.is_empty() {
         if let Err(e) = app.listen(TlsListener::build()
                                        .addrs(listen)
                                        .cert(tide_cert_path)